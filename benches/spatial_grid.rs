@@ -0,0 +1,116 @@
+//! Compares the naive all-pairs collision check with the `SpatialGrid` broad phase
+//!
+//! Run with `cargo bench --bench spatial_grid`.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+#[path = "../src/spatial.rs"]
+mod spatial;
+
+use spatial::SpatialGrid;
+
+const ENTITY_COUNTS: [usize; 5] = [50, 100, 200, 400, 800];
+const ITERATIONS: u32 = 200;
+/// Roughly the visible play field
+const FIELD_SIZE: Vec2 = Vec2::new(800.0, 600.0);
+
+struct Collider {
+    entity: Entity,
+    center: Vec2,
+    size: Vec2,
+}
+
+fn overlaps(a: &Collider, b: &Collider) -> bool {
+    let distance = (a.center - b.center).abs();
+    let reach = (a.size + b.size) / 2.0;
+
+    distance.x < reach.x && distance.y < reach.y
+}
+
+fn random_colliders(count: usize) -> Vec<Collider> {
+    let mut rng = rand::thread_rng();
+
+    (0..count)
+        .map(|index| Collider {
+            entity: Entity::from_raw(index as u32),
+            center: Vec2::new(
+                rng.gen_range(0.0..FIELD_SIZE.x),
+                rng.gen_range(0.0..FIELD_SIZE.y),
+            ),
+            // mix of shuriken sized and enemy sized boxes
+            size: if rng.gen_bool(0.5) {
+                Vec2::splat(8.0)
+            } else {
+                Vec2::new(30.0, 48.0)
+            },
+        })
+        .collect()
+}
+
+fn naive(colliders: &[Collider]) -> usize {
+    let mut hits = 0;
+
+    for (index, a) in colliders.iter().enumerate() {
+        for b in &colliders[index + 1..] {
+            if overlaps(a, b) {
+                hits += 1;
+            }
+        }
+    }
+
+    hits
+}
+
+fn grid(spatial_grid: &mut SpatialGrid, colliders: &[Collider]) -> usize {
+    spatial_grid.clear();
+    for collider in colliders {
+        spatial_grid.insert(collider.entity, collider.center, collider.size);
+    }
+
+    let mut hits = 0;
+    for a in colliders {
+        for candidate in spatial_grid.query(a.center, a.size) {
+            // colliders were created in entity order, so the index lookup is direct
+            let b = &colliders[candidate.index() as usize];
+            if a.entity < b.entity && overlaps(a, b) {
+                hits += 1;
+            }
+        }
+    }
+
+    hits
+}
+
+fn time(mut run: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut hits = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        hits = run();
+    }
+
+    (start.elapsed() / ITERATIONS, hits)
+}
+
+fn main() {
+    let mut spatial_grid = SpatialGrid::default();
+
+    println!(
+        "{:>8} {:>14} {:>14} {:>8}",
+        "entities", "naive", "grid", "hits"
+    );
+    for count in ENTITY_COUNTS {
+        let colliders = random_colliders(count);
+
+        let (naive_time, naive_hits) = time(|| naive(&colliders));
+        let (grid_time, grid_hits) = time(|| grid(&mut spatial_grid, &colliders));
+        assert_eq!(naive_hits, grid_hits, "broad phase missed a collision");
+
+        println!(
+            "{:>8} {:>14?} {:>14?} {:>8}",
+            count, naive_time, grid_time, grid_hits
+        );
+    }
+}
@@ -45,6 +45,7 @@ pub struct AssetHandler<'w, 's> {
     asset_server: Res<'w, AssetServer>,
     asset_loading: ResMut<'w, AssetLoading>,
     texture_atlases: ResMut<'w, Assets<TextureAtlas>>,
+    tiled_maps: Res<'w, Assets<TiledMap>>,
     state: ResMut<'w, State<GameState>>,
     next_state: ResMut<'w, NextState>,
 
@@ -94,6 +95,11 @@ impl<'w, 's> AssetHandler<'w, 's> {
         self.add_asset(font, path);
     }
 
+    /// The tileset images the map points to are waited on once the map itself is loaded
+    pub fn add_tiled_map(&mut self, tiled_map: &mut Handle<TiledMap>, path: &str) {
+        self.add_asset(tiled_map, path);
+    }
+
     fn queue_assets_for_state(
         &mut self,
         game_state: &GameState,
@@ -140,6 +146,32 @@ fn check_assets(mut asset_handler: AssetHandler) {
         }
     }
 
+    // a map only knows its tileset images once it is parsed, so wait on those as well
+    let tileset_images = asset_handler
+        .asset_loading
+        .asset_handles
+        .iter()
+        .filter_map(|(handle, path)| {
+            let tiled_map = asset_handler.tiled_maps.get(&handle.typed_weak::<TiledMap>())?;
+            Some(tiled_map.tilesets.iter().map(move |tileset| {
+                (tileset.image.clone_untyped(), format!("{} tileset", path))
+            }))
+        })
+        .flatten()
+        .filter(|(image, _)| {
+            !asset_handler
+                .asset_loading
+                .asset_handles
+                .iter()
+                .any(|(handle, _)| handle == image)
+        })
+        .collect::<Vec<_>>();
+
+    if !tileset_images.is_empty() {
+        ready = false;
+        asset_handler.asset_loading.asset_handles.extend(tileset_images);
+    }
+
     if ready {
         println!("ready!");
         println!("{:?}", asset_handler.next_state.state);
@@ -1,12 +1,23 @@
+use std::collections::HashMap;
+
 use crate::{asset_loading::AssetHandler, prelude::*};
 
 #[derive(Default, Debug, Resource)]
 pub struct GameAssets {
     pub musashi: Handle<TextureAtlas>,
     pub red_ninja: Handle<TextureAtlas>,
+    pub shield_ninja: Handle<TextureAtlas>,
+    pub thrower_ninja: Handle<TextureAtlas>,
+    pub diver_ninja: Handle<TextureAtlas>,
+    pub armored_ninja: Handle<TextureAtlas>,
+    pub boss_ninja: Handle<TextureAtlas>,
+    pub yamato: Handle<TextureAtlas>,
     pub death_effect: Handle<TextureAtlas>,
 
     pub shuriken: Handle<Image>,
+    pub power_up: Handle<Image>,
+    pub bomb: Handle<Image>,
+    pub enemy_projectile: Handle<Image>,
 
     pub background: Handle<Image>,
     pub left_roof: Handle<Image>,
@@ -19,8 +30,12 @@ pub struct GameAssets {
     pub shuriken_sound: Handle<KiraAudioSource>,
     pub reflection_sound: Handle<KiraAudioSource>,
     pub melee_attack_sound: Handle<KiraAudioSource>,
+    pub ninjutsu_sound: Handle<KiraAudioSource>,
+    pub clank_sound: Handle<KiraAudioSource>,
+    pub pickup_sound: Handle<KiraAudioSource>,
 
     pub ingame_bgm: Handle<KiraAudioSource>,
+    pub boss_bgm: Handle<KiraAudioSource>,
     pub title_screen_bgm: Handle<KiraAudioSource>,
 
     pub menu_font: Handle<Font>,
@@ -30,6 +45,9 @@ pub struct GameAssets {
     pub splash_screen: Handle<Image>,
     pub transition: Handle<TextureAtlas>,
     pub title_screen: Handle<Image>,
+
+    /// Tiled maps keyed by their path under `assets/`
+    pub tiled_maps: HashMap<String, Handle<TiledMap>>,
 }
 
 pub struct AssetsPlugin;
@@ -56,6 +74,48 @@ impl AssetsPlugin {
             4,
             1,
         );
+        asset_handler.add_texture_atlas(
+            &mut game_assets.shield_ninja,
+            "sprites/enemy/shield_ninja.png",
+            Vec2::new(40.0, 65.0),
+            6,
+            1,
+        );
+        asset_handler.add_texture_atlas(
+            &mut game_assets.thrower_ninja,
+            "sprites/enemy/thrower_ninja.png",
+            Vec2::new(40.0, 65.0),
+            4,
+            1,
+        );
+        asset_handler.add_texture_atlas(
+            &mut game_assets.diver_ninja,
+            "sprites/enemy/diver_ninja.png",
+            Vec2::new(40.0, 65.0),
+            4,
+            1,
+        );
+        asset_handler.add_texture_atlas(
+            &mut game_assets.armored_ninja,
+            "sprites/enemy/armored_ninja.png",
+            Vec2::new(40.0, 65.0),
+            4,
+            1,
+        );
+        asset_handler.add_texture_atlas(
+            &mut game_assets.boss_ninja,
+            "sprites/enemy/boss_ninja.png",
+            Vec2::new(80.0, 120.0),
+            4,
+            1,
+        );
+        asset_handler.add_texture_atlas(
+            &mut game_assets.yamato,
+            "sprites/player/yamato.png",
+            Vec2::new(48.0, 32.0),
+            4,
+            1,
+        );
         asset_handler.add_texture_atlas(
             &mut game_assets.death_effect,
             "sprites/effects/death.png",
@@ -65,6 +125,9 @@ impl AssetsPlugin {
         );
 
         asset_handler.add_sprites(&mut game_assets.shuriken, "objects/shuriken.png");
+        asset_handler.add_sprites(&mut game_assets.power_up, "objects/power_up.png");
+        asset_handler.add_sprites(&mut game_assets.bomb, "objects/bomb.png");
+        asset_handler.add_sprites(&mut game_assets.enemy_projectile, "objects/kunai.png");
 
         asset_handler.add_sprites(&mut game_assets.background, "background/background_day.png");
         asset_handler.add_sprites(&mut game_assets.left_roof, "background/left_roof.png");
@@ -77,11 +140,15 @@ impl AssetsPlugin {
         asset_handler.add_audio(&mut game_assets.shuriken_sound, "sfx/shuriken.ogg");
         asset_handler.add_audio(&mut game_assets.reflection_sound, "sfx/parry.ogg");
         asset_handler.add_audio(&mut game_assets.melee_attack_sound, "sfx/melee_attack.ogg");
+        asset_handler.add_audio(&mut game_assets.ninjutsu_sound, "sfx/ninjutsu.ogg");
+        asset_handler.add_audio(&mut game_assets.clank_sound, "sfx/clank.ogg");
+        asset_handler.add_audio(&mut game_assets.pickup_sound, "sfx/pickup.ogg");
 
         asset_handler.add_audio(
             &mut game_assets.ingame_bgm,
             "music/08 - Bonus Stage - Keisuke Tsukahara.ogg",
         );
+        asset_handler.add_audio(&mut game_assets.boss_bgm, "music/boss.ogg");
 
         asset_handler.add_font(
             &mut game_assets.menu_font,
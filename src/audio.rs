@@ -14,12 +14,16 @@ pub enum SFXEvents {
     ShurikenSound,
     ReflectionSound,
     MeleeAttackSound,
+    NinjutsuSound,
+    ClankSound,
+    PickupSound,
     MenuSFX,
 }
 
 pub enum BGMEvents {
     TitleScreenMusic,
     InGameMusic,
+    BossMusic,
     // EndStageMusic,
 }
 
@@ -67,6 +71,15 @@ fn play_sfx(
             SFXEvents::MeleeAttackSound => {
                 audio.play(game_assets.melee_attack_sound.clone());
             }
+            SFXEvents::NinjutsuSound => {
+                audio.play(game_assets.ninjutsu_sound.clone());
+            }
+            SFXEvents::ClankSound => {
+                audio.play(game_assets.clank_sound.clone());
+            }
+            SFXEvents::PickupSound => {
+                audio.play(game_assets.pickup_sound.clone());
+            }
             SFXEvents::MenuSFX => {
                 audio.play(game_assets.menu_sfx.clone());
             }
@@ -89,8 +102,16 @@ fn play_bgm(
                 audio.play(game_assets.title_screen_bgm.clone());
             }
             BGMEvents::InGameMusic => {
+                // the channel is still paused from the previous stage fading out
+                audio.stop();
+                audio.resume();
                 audio.play(game_assets.ingame_bgm.clone());
             }
+            BGMEvents::BossMusic => {
+                audio.stop();
+                audio.resume();
+                audio.play(game_assets.boss_bgm.clone());
+            }
         }
     }
 
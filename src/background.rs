@@ -10,6 +10,10 @@ pub struct BackgroundBundle {
 #[derive(Resource, Debug)]
 pub struct BonusStageTimer(pub Timer);
 
+/// Black sprite fading in over the stage once it ends
+#[derive(Component)]
+pub struct EndStageFadeout;
+
 impl Default for BonusStageTimer {
     fn default() -> Self {
         BonusStageTimer(Timer::from_seconds(55.0, TimerMode::Once))
@@ -46,8 +50,17 @@ pub struct BackgroundPlugin;
 impl Plugin for BackgroundPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<BonusStageTimer>()
-            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_background))
+            .add_system_set(
+                SystemSet::on_enter(GameState::InGame)
+                    .with_system(spawn_background)
+                    .with_system(reset_bonus_stage_timer),
+            )
             .add_system_set(SystemSet::on_enter(GameState::EndStage).with_system(end_stage_background_fadeout))
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage)
+                    .with_system(despawner::<Background>)
+                    .with_system(despawner::<EndStageFadeout>),
+            )
             .add_system_set(
                 SystemSet::on_update(GameState::InGame)
                     .with_run_criteria(pause_game)
@@ -61,6 +74,10 @@ pub fn spawn_background(mut commands: Commands, game_assets: Res<GameAssets>) {
     commands.spawn(BackgroundBundle::new(game_assets));
 }
 
+pub fn reset_bonus_stage_timer(mut stopwatch: ResMut<BonusStageTimer>) {
+    stopwatch.0.reset();
+}
+
 pub fn background_animator(mut query: Query<&mut Transform, With<Background>>, time: Res<Time>) {
     for mut background_transform in query.iter_mut() {
         background_transform.translation.y += (FALLING_SPEED / 100.0) * time.delta_seconds();
@@ -70,18 +87,30 @@ pub fn background_animator(mut query: Query<&mut Transform, With<Background>>, t
 pub fn bonus_stage_transition(
     time: Res<Time>,
     mut stopwatch: ResMut<BonusStageTimer>,
-    mut query: Query<&Player>,
+    query: Query<&Player>,
     mut game_state: ResMut<State<GameState>>,
     pause_event: Res<PauseEvent>,
+    boss_config: Res<BossConfig>,
+    mut boss_encounter: ResMut<BossEncounter>,
+    game_mode: Res<GameMode>,
 ) {
-    let player = query.single_mut();
+    // endless runs only end when the player runs out of lives
+    if *game_mode != GameMode::Story && *game_mode != GameMode::Coop {
+        return;
+    }
 
-    if player.1 == LevelState::Start && *pause_event == PauseEvent::Unpaused {
+    let started = query.iter().any(|player| player.1 == LevelState::Start);
+
+    if started && *pause_event == PauseEvent::Unpaused {
         stopwatch.0.tick(time.delta());
     }
 
     if stopwatch.0.just_finished() {
-        game_state.set(GameState::EndStage).unwrap();
+        if boss_config.enabled {
+            *boss_encounter = BossEncounter::Pending;
+        } else {
+            end_stage(&mut game_state);
+        }
     }
 }
 
@@ -122,5 +151,6 @@ pub fn end_stage_background_fadeout(mut commands: Commands) {
                 duration: std::time::Duration::from_secs(1),
             },
         ),
+        EndStageFadeout,
     ));
 }
@@ -0,0 +1,119 @@
+use crate::prelude::*;
+
+pub const BOMB_DEFUSE_BONUS: u32 = 2000;
+
+/// Time bombs left in the current platforming stage
+#[derive(Resource, Default, Debug)]
+pub struct StageObjective {
+    pub total: usize,
+    pub defused: usize,
+}
+
+impl StageObjective {
+    pub fn new(total: usize) -> Self {
+        StageObjective { total, defused: 0 }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.defused >= self.total
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Bomb;
+
+#[derive(Component)]
+struct OnBombTally;
+
+#[derive(Bundle)]
+pub struct BombBundle {
+    bomb: Bomb,
+    hitbox: HitBox,
+    collision_layers: CollisionLayers,
+    sprite_bundle: SpriteBundle,
+}
+
+impl BombBundle {
+    pub fn new(game_assets: &Res<GameAssets>, position: Vec2) -> Self {
+        BombBundle {
+            bomb: Bomb,
+            hitbox: HitBox(Vec2::new(20.0, 20.0)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Pickup,
+                &[CollisionLayer::Player],
+            ),
+            sprite_bundle: SpriteBundle {
+                texture: game_assets.bomb.clone(),
+                transform: Transform::from_translation(position.extend(1.5)),
+                ..default()
+            },
+        }
+    }
+}
+
+pub struct BombsPlugin;
+impl Plugin for BombsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StageObjective>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Platforming)
+                    .with_system(bomb_defusal.after(detect_collisions)),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::EndStage).with_system(bomb_tally))
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage)
+                    .with_system(despawner::<OnBombTally>)
+                    .with_system(despawner::<Bomb>),
+            );
+    }
+}
+
+/// Touching a bomb defuses it
+pub fn bomb_defusal(
+    mut commands: Commands,
+    mut player_touched_pickup_events: EventReader<PlayerTouchedPickupEvent>,
+    mut stage_objective: ResMut<StageObjective>,
+    bomb_query: Query<(), (With<Bomb>, Without<MarkDespawn>)>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for event in player_touched_pickup_events.iter() {
+        if !bomb_query.contains(event.pickup) {
+            continue;
+        }
+
+        commands.entity(event.pickup).insert(MarkDespawn);
+        stage_objective.defused += 1;
+        sfx_events.send(SFXEvents::ClankSound);
+    }
+}
+
+/// Awards the bonus for every defused bomb and shows it on the end of stage screen
+fn bomb_tally(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut score: ResMut<Score>,
+    mut stage_objective: ResMut<StageObjective>,
+) {
+    if stage_objective.total == 0 {
+        return;
+    }
+
+    let bonus = stage_objective.defused as u32 * BOMB_DEFUSE_BONUS;
+    score.0 += bonus;
+
+    commands
+        .spawn((results_screen(), OnBombTally))
+        .with_children(|parent| {
+            parent.spawn(results_text(
+                format!(
+                    "BOMBS DEFUSED {} x {} = {}",
+                    stage_objective.defused, BOMB_DEFUSE_BONUS, bonus
+                ),
+                &game_assets,
+            ));
+            parent.spawn(results_text(format!("SCORE {}", score.0), &game_assets));
+        });
+
+    // the next stage sets its own objective, this keeps bonus stages from paying out again
+    *stage_objective = StageObjective::default();
+}
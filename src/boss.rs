@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+
+use crate::{pause_game, prelude::*};
+use ron::de::from_bytes;
+use serde::Deserialize;
+
+pub const BOSS_KILL_SCORE: u32 = 5000;
+pub const BOSS_AIMED_BURST_SPACING_DEG: f32 = 8.0;
+pub const BOSS_HEALTH_BAR_WIDTH: f32 = 200.0;
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum BossPattern {
+    /// Projectiles thrown straight at the player
+    Aimed { burst: u32, speed: f32 },
+    /// A fan of projectiles centered on the player
+    Fan {
+        count: u32,
+        spread_deg: f32,
+        speed: f32,
+    },
+    /// Projectiles thrown evenly in every direction
+    Ring { count: u32, speed: f32 },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossPhase {
+    /// The phase starts once the boss health drops to this fraction of its maximum
+    pub health_fraction: f32,
+    pub attack_interval_sec: f32,
+    pub pattern: BossPattern,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossEntrance {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    pub duration_sec: f32,
+}
+
+#[derive(Resource, Deserialize, Clone, Debug)]
+pub struct BossConfig {
+    pub enabled: bool,
+    pub health: u32,
+    pub hitbox: (f32, f32),
+    pub entrance: BossEntrance,
+    pub sway_speed: f32,
+    pub phases: Vec<BossPhase>,
+}
+
+impl BossConfig {
+    /// Index of the phase matching the remaining health of the boss
+    pub fn phase_index(&self, health: u32) -> usize {
+        let health_fraction = health as f32 / self.health.max(1) as f32;
+
+        self.phases
+            .iter()
+            .rposition(|phase| health_fraction <= phase.health_fraction)
+            .unwrap_or_default()
+    }
+
+    /// Checks the phases can all be reached, `phase_index` relies on them going from the highest
+    /// health fraction down
+    pub fn validate(&self) -> Result<(), String> {
+        if self.phases.is_empty() {
+            return Err("needs at least one phase".to_string());
+        }
+
+        for (index, phase) in self.phases.iter().enumerate() {
+            if !(0.0..=1.0).contains(&phase.health_fraction) {
+                return Err(format!(
+                    "phase {} health_fraction {} is outside of 0..=1",
+                    index, phase.health_fraction
+                ));
+            }
+        }
+
+        for (index, pair) in self.phases.windows(2).enumerate() {
+            if pair[1].health_fraction >= pair[0].health_fraction {
+                return Err(format!(
+                    "phase {} health_fraction {} has to be below the {} of the phase before it",
+                    index + 1,
+                    pair[1].health_fraction,
+                    pair[0].health_fraction
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Progress of the boss fight closing the bonus stage
+#[derive(Resource, Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BossEncounter {
+    #[default]
+    Waiting,
+    Pending,
+    Entering,
+    Fighting,
+    Defeated,
+}
+
+#[derive(Component, Debug)]
+pub struct Boss {
+    pub phase: usize,
+    pub attack_timer: Timer,
+    pub sway_direction: f32,
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct BossEntranceTimer(pub Timer);
+
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Bundle)]
+pub struct BossBundle {
+    boss: Boss,
+    health: Health,
+    hitbox: HitBox,
+    collision_layers: CollisionLayers,
+    entrance_timer: BossEntranceTimer,
+    sprite_bundle: SpriteSheetBundle,
+}
+
+impl BossBundle {
+    pub fn new(game_assets: &Res<GameAssets>, boss_config: &BossConfig) -> Self {
+        let (start_x, start_y) = boss_config.entrance.start;
+        let (hitbox_x, hitbox_y) = boss_config.hitbox;
+
+        BossBundle {
+            boss: Boss {
+                phase: 0,
+                attack_timer: Timer::from_seconds(
+                    boss_config.phases[0].attack_interval_sec,
+                    TimerMode::Repeating,
+                ),
+                sway_direction: 1.0,
+            },
+            health: Health(boss_config.health),
+            hitbox: HitBox(Vec2::new(hitbox_x, hitbox_y)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Enemy,
+                &[CollisionLayer::PlayerProjectile],
+            ),
+            entrance_timer: BossEntranceTimer(Timer::from_seconds(
+                boss_config.entrance.duration_sec,
+                TimerMode::Once,
+            )),
+            sprite_bundle: SpriteSheetBundle {
+                texture_atlas: game_assets.boss_ninja.clone(),
+                transform: Transform::from_translation(Vec3::new(start_x, start_y, 1.5)),
+                ..default()
+            },
+        }
+    }
+}
+
+pub struct BossPlugin;
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        let boss_config: BossConfig = from_bytes(include_bytes!("../data/boss.ron")).unwrap();
+
+        if let Err(error) = boss_config.validate() {
+            panic!("Invalid data/boss.ron: {}", error);
+        }
+
+        app.insert_resource(boss_config)
+            .init_resource::<BossEncounter>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::InGame).with_system(reset_boss_encounter),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(spawn_boss)
+                    .with_system(boss_entrance)
+                    .with_system(boss_movement)
+                    .with_system(boss_attack),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(boss_hit.after(detect_collisions))
+                    .with_system(boss_death)
+                    .with_system(update_boss_health_bar),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::EndStage).with_system(despawner::<Boss>),
+            );
+    }
+}
+
+pub fn reset_boss_encounter(mut boss_encounter: ResMut<BossEncounter>) {
+    *boss_encounter = BossEncounter::default();
+}
+
+pub fn spawn_boss(
+    mut commands: Commands,
+    mut boss_encounter: ResMut<BossEncounter>,
+    boss_config: Res<BossConfig>,
+    game_assets: Res<GameAssets>,
+    mut bgm_events: EventWriter<BGMEvents>,
+) {
+    if *boss_encounter != BossEncounter::Pending {
+        return;
+    }
+
+    let (end_x, end_y) = boss_config.entrance.end;
+    let boss_bundle = BossBundle::new(&game_assets, &boss_config);
+    let entrance = boss_bundle.sprite_bundle.transform.ease_to(
+        Transform::from_translation(Vec3::new(end_x, end_y, 1.5)),
+        EaseFunction::QuadraticOut,
+        EasingType::Once {
+            duration: Duration::from_secs_f32(boss_config.entrance.duration_sec),
+        },
+    );
+
+    commands.spawn((boss_bundle, entrance));
+    spawn_boss_health_bar(&mut commands);
+
+    bgm_events.send(BGMEvents::BossMusic);
+    *boss_encounter = BossEncounter::Entering;
+}
+
+fn spawn_boss_health_bar(commands: &mut Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Percent(50.0),
+                        bottom: Val::Px(20.0),
+                        ..default()
+                    },
+                    margin: UiRect::left(Val::Px(-BOSS_HEALTH_BAR_WIDTH / 2.0)),
+                    size: Size::new(Val::Px(BOSS_HEALTH_BAR_WIDTH), Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                ..default()
+            },
+            OnHud,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    background_color: Color::RED.into(),
+                    ..default()
+                },
+                BossHealthBar,
+            ));
+        });
+}
+
+/// Keeps the boss passive until it has reached its spot on screen
+pub fn boss_entrance(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut boss_encounter: ResMut<BossEncounter>,
+    mut query: Query<(Entity, &mut BossEntranceTimer), With<Boss>>,
+) {
+    for (entity, mut entrance_timer) in query.iter_mut() {
+        if entrance_timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<BossEntranceTimer>();
+            *boss_encounter = BossEncounter::Fighting;
+        }
+    }
+}
+
+pub fn boss_movement(
+    time: Res<Time>,
+    boss_config: Res<BossConfig>,
+    mut query: Query<(&mut Transform, &mut Boss, &HitBox), Without<BossEntranceTimer>>,
+) {
+    for (mut transform, mut boss, hitbox) in query.iter_mut() {
+        transform.translation.x +=
+            boss.sway_direction * boss_config.sway_speed * time.delta_seconds();
+
+        let Bounds { right, left, .. } = calculate_bounds(&transform, Some(hitbox.0));
+        if (left < LEFT_WALL && boss.sway_direction < 0.0)
+            || (right > RIGHT_WALL && boss.sway_direction > 0.0)
+        {
+            boss.sway_direction *= -1.0;
+        }
+    }
+}
+
+pub fn boss_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    boss_config: Res<BossConfig>,
+    game_assets: Res<GameAssets>,
+    player_query: Query<&Transform, With<Player>>,
+    mut boss_query: Query<(&Transform, &Health, &mut Boss), Without<BossEntranceTimer>>,
+) {
+    for (transform, health, mut boss) in boss_query.iter_mut() {
+        let phase_index = boss_config.phase_index(health.0);
+        let phase = &boss_config.phases[phase_index];
+
+        if phase_index != boss.phase {
+            boss.phase = phase_index;
+            boss.attack_timer =
+                Timer::from_seconds(phase.attack_interval_sec, TimerMode::Repeating);
+        }
+
+        if !boss.attack_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let aim = match nearest_player(&player_query, position) {
+            Some(player_position) => player_position - position,
+            None => continue,
+        };
+        for (direction, speed) in pattern_directions(&phase.pattern, aim) {
+            commands.spawn(EnemyProjectileBundle::new(
+                &game_assets,
+                transform.translation,
+                direction,
+                speed,
+            ));
+        }
+    }
+}
+
+/// Directions and speeds of every projectile in one attack of `pattern`
+fn pattern_directions(pattern: &BossPattern, aim: Vec2) -> Vec<(Vec2, f32)> {
+    let aim = aim.try_normalize().unwrap_or(Vec2::Y);
+
+    let fan = |count: u32, spread: f32, speed: f32| {
+        let step = if count > 1 {
+            spread / (count - 1) as f32
+        } else {
+            0.0
+        };
+
+        (0..count)
+            .map(|index| {
+                let angle = -spread / 2.0 + step * index as f32;
+                (Vec2::from_angle(angle).rotate(aim), speed)
+            })
+            .collect::<Vec<(Vec2, f32)>>()
+    };
+
+    match *pattern {
+        BossPattern::Aimed { burst, speed } => fan(
+            burst,
+            BOSS_AIMED_BURST_SPACING_DEG.to_radians() * burst.saturating_sub(1) as f32,
+            speed,
+        ),
+        BossPattern::Fan {
+            count,
+            spread_deg,
+            speed,
+        } => fan(count, spread_deg.to_radians(), speed),
+        BossPattern::Ring { count, speed } => (0..count)
+            .map(|index| {
+                let angle = std::f32::consts::TAU * index as f32 / count as f32;
+                (Vec2::from_angle(angle).rotate(aim), speed)
+            })
+            .collect(),
+    }
+}
+
+/// Shurikens and melee slashes chip away at the boss once it has finished its entrance
+pub fn boss_hit(
+    mut commands: Commands,
+    mut projectile_hit_enemy_events: EventReader<ProjectileHitEnemyEvent>,
+    shuriken_query: Query<
+        Option<&Piercing>,
+        (With<Shuriken>, Without<MarkDespawn>, Without<Reflected>),
+    >,
+    mut slash_query: Query<&mut SlashedEnemies, (With<MeleeSlash>, Without<MarkDespawn>)>,
+    mut boss_query: Query<
+        (
+            &mut Health,
+            &mut TextureAtlasSprite,
+            Option<&mut HitFlashTimer>,
+        ),
+        (With<Boss>, Without<BossEntranceTimer>, Without<MarkDespawn>),
+    >,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    // hits landing together are added up so the boss only flashes once
+    let mut damage = HashMap::new();
+
+    for event in projectile_hit_enemy_events.iter() {
+        if !boss_query.contains(event.enemy) {
+            continue;
+        }
+
+        let hit_damage = if let Ok(piercing) = shuriken_query.get(event.projectile) {
+            if piercing.is_none() {
+                commands.entity(event.projectile).insert(MarkDespawn);
+            }
+            SHURIKEN_DAMAGE
+        } else if let Ok(mut slashed_enemies) = slash_query.get_mut(event.projectile) {
+            if slashed_enemies.0.contains(&event.enemy) {
+                continue;
+            }
+            slashed_enemies.0.push(event.enemy);
+            MELEE_DAMAGE
+        } else {
+            continue;
+        };
+
+        *damage.entry(event.enemy).or_insert(0) += hit_damage;
+    }
+
+    for (boss_entity, damage) in damage {
+        let (mut health, mut sprite, hit_flash) = match boss_query.get_mut(boss_entity) {
+            Ok(boss) => boss,
+            Err(_) => continue,
+        };
+
+        if !health.damage(damage) {
+            start_hit_flash(&mut commands, boss_entity, &mut sprite, hit_flash);
+            sfx_events.send(SFXEvents::ClankSound);
+        } else {
+            sfx_events.send(SFXEvents::CollisionSound);
+        }
+    }
+}
+
+pub fn boss_death(
+    mut commands: Commands,
+    mut boss_encounter: ResMut<BossEncounter>,
+    mut game_state: ResMut<State<GameState>>,
+    mut score: ResMut<Score>,
+    mut sfx_events: EventWriter<SFXEvents>,
+    game_assets: Res<GameAssets>,
+    query: Query<(Entity, &Transform, &Health), (With<Boss>, Without<MarkDespawn>)>,
+) {
+    for (entity, transform, health) in query.iter() {
+        if health.0 > 0 {
+            continue;
+        }
+
+        commands.entity(entity).insert(MarkDespawn);
+        commands.spawn(DeathEffectBundle::new(&game_assets, transform.translation));
+        sfx_events.send(SFXEvents::DeathSound);
+
+        score.0 += BOSS_KILL_SCORE;
+        *boss_encounter = BossEncounter::Defeated;
+        end_stage(&mut game_state);
+    }
+}
+
+pub fn update_boss_health_bar(
+    boss_config: Res<BossConfig>,
+    boss_query: Query<&Health, (With<Boss>, Changed<Health>)>,
+    mut bar_query: Query<&mut Style, With<BossHealthBar>>,
+) {
+    for health in boss_query.iter() {
+        let health_fraction = health.0 as f32 / boss_config.health.max(1) as f32;
+
+        for mut style in bar_query.iter_mut() {
+            style.size.width = Val::Percent(health_fraction * 100.0);
+        }
+    }
+}
@@ -1,109 +1,453 @@
-use rand::Rng;
-
-use crate::{assets::GameAssets, prelude::*};
+use crate::prelude::*;
 
 pub struct CollisionPlugin;
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_system(collision_system)
-                .with_system(player_collision),
-        );
+        app.init_resource::<SpatialGrid>()
+            .add_event::<ProjectileHitEnemyEvent>()
+            .add_event::<ProjectileHitPlayerEvent>()
+            .add_event::<PlayerTouchedPickupEvent>()
+            .add_event::<EnemyTouchedPlayerEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(reflected_projectile_layers.before(detect_collisions))
+                    .with_system(
+                        update_spatial_grid
+                            .after(shuriken_movement)
+                            .after(enemy_projectile_movement),
+                    )
+                    .with_system(detect_collisions.after(update_spatial_grid))
+                    .with_system(enemy_contact_collision.after(detect_collisions)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Platforming)
+                    .with_system(reflected_projectile_layers.before(detect_collisions))
+                    .with_system(update_spatial_grid.after(platform_projectile_movement))
+                    .with_system(detect_collisions.after(update_spatial_grid)),
+            );
     }
 }
 
-pub fn collision_system(
-    mut commands: Commands,
-    player_query: Query<&Transform, With<Player>>,
-    shuriken_query: Query<
-        (Entity, &Transform, &HitBox),
-        (With<Shuriken>, Without<MarkDespawn>, Without<Reflected>),
-    >,
-    mut enemy_query: Query<
-        (Entity, &Transform, &HitBox, &mut Enemy, &ReflectChance),
-        (With<Enemy>, Without<MarkDespawn>),
-    >,
-    mut sfx_events: EventWriter<SFXEvents>,
+/// Groups of colliders, used to decide which pairs are checked at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionLayer {
+    Player,
+    Enemy,
+    PlayerProjectile,
+    EnemyProjectile,
+    Pickup,
+}
+
+impl CollisionLayer {
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// The layer a collider is on and the layers it can collide with
+#[derive(Component, Debug, Clone, Copy)]
+pub struct CollisionLayers {
+    pub layer: CollisionLayer,
+    mask: u32,
+}
+
+impl CollisionLayers {
+    pub fn new(layer: CollisionLayer, mask: &[CollisionLayer]) -> Self {
+        CollisionLayers {
+            layer,
+            mask: mask.iter().fold(0, |mask, layer| mask | layer.bit()),
+        }
+    }
+
+    /// Whether both colliders accept the other's layer
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.mask & other.layer.bit() != 0 && other.mask & self.layer.bit() != 0
+    }
+}
+
+/// A shuriken or melee slash touched an enemy or the boss
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectileHitEnemyEvent {
+    pub projectile: Entity,
+    pub enemy: Entity,
+}
+
+/// An enemy projectile or a reflected shuriken touched a player
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectileHitPlayerEvent {
+    pub projectile: Entity,
+    pub player: Entity,
+}
+
+/// A player touched a power up, score pickup or bomb
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerTouchedPickupEvent {
+    pub player: Entity,
+    pub pickup: Entity,
+}
+
+/// An enemy body touched a player
+#[derive(Debug, Clone, Copy)]
+pub struct EnemyTouchedPlayerEvent {
+    pub enemy: Entity,
+    pub player: Entity,
+}
 
-    game_assets: Res<GameAssets>,
+/// Rebuilds the broad phase from every collider still in play
+///
+/// Projectiles are inserted with the whole path of their last step.
+pub fn update_spatial_grid(
+    mut spatial_grid: ResMut<SpatialGrid>,
+    query: Query<(Entity, &Transform, &HitBox, Option<&PreviousPosition>), Without<MarkDespawn>>,
 ) {
-    let player_transform = player_query.single();
+    spatial_grid.clear();
 
-    for (shuriken_entity, shuriken_transform, shuriken_hitbox) in shuriken_query.iter() {
-        let shurkien_scale = shuriken_transform.scale.xy();
+    for (entity, transform, hitbox, previous_position) in query.iter() {
+        let position = transform.translation.truncate();
+        let start = previous_position.map_or(position, |previous| previous.0);
+        let (center, size) = swept_bounds(start, position, hitbox.0 * transform.scale.xy());
 
-        for (enemy_entity, enemy_transform, enemy_hitbox, mut enemy, reflect_chance) in
-            enemy_query.iter_mut()
-        {
-            let random_number = rand::thread_rng().gen_range(0.0..=1.0);
+        spatial_grid.insert(entity, center, size);
+    }
+}
 
-            let enemy_scale = enemy_transform.scale.xy();
+/// Center and size of the box covering a collider over its whole step from `start` to `end`
+pub fn swept_bounds(start: Vec2, end: Vec2, size: Vec2) -> (Vec2, Vec2) {
+    ((start + end) / 2.0, size + (end - start).abs())
+}
 
-            let collision = collide(
-                shuriken_transform.translation,
-                shuriken_hitbox.0 * shurkien_scale,
-                enemy_transform.translation,
-                enemy_hitbox.0 * enemy_scale,
-            );
+/// How far along its step from `start` to `end` a box of `size` first touches the target box
+///
+/// Returns a fraction between 0 and 1, or `None` if the boxes never touch during the step, so
+/// fast projectiles can't pass through anything between two frames.
+pub fn swept_collide(
+    start: Vec2,
+    end: Vec2,
+    size: Vec2,
+    target: Vec2,
+    target_size: Vec2,
+) -> Option<f32> {
+    // growing the target by the moving box turns this into a ray cast
+    let half_size = (size + target_size) / 2.0;
+    let min = target - half_size;
+    let max = target + half_size;
+    let delta = end - start;
 
-            if collision.is_some() {
-                let angle = f32::atan2(
-                    player_transform.translation.y - shuriken_transform.translation.y,
-                    player_transform.translation.x - shuriken_transform.translation.x,
-                );
-
-                if random_number < reflect_chance.0 && enemy.0 == EnemyState::Airborne {
-                    commands.entity(shuriken_entity).insert(Reflected(angle));
-                    sfx_events.send(SFXEvents::ReflectionSound);
-                } else if enemy.0 == EnemyState::WallHanging {
-                    commands.entity(shuriken_entity).insert(Reflected(angle));
-                    sfx_events.send(SFXEvents::ReflectionSound);
-                } else {
-                    sfx_events.send(SFXEvents::CollisionSound);
-
-                    commands.entity(enemy_entity).insert(MarkDespawn);
-
-                    commands.entity(shuriken_entity).insert(MarkDespawn);
-
-                    commands.spawn(DeathEffectBundle::new(
-                        &game_assets,
-                        enemy_transform.translation,
-                    ));
-                    println!("Entity {:?} died.", enemy_entity);
-                    sfx_events.send(SFXEvents::DeathSound);
-
-                    enemy.0 = EnemyState::Dead;
-                }
+    let mut entry = 0.0_f32;
+    let mut exit = 1.0_f32;
+    for axis in 0..2 {
+        if delta[axis] == 0.0 {
+            if start[axis] <= min[axis] || start[axis] >= max[axis] {
+                return None;
             }
+            continue;
+        }
+
+        let near = (min[axis] - start[axis]) / delta[axis];
+        let far = (max[axis] - start[axis]) / delta[axis];
+        entry = entry.max(near.min(far));
+        exit = exit.min(near.max(far));
+
+        if entry >= exit {
+            return None;
         }
     }
+
+    Some(entry)
 }
 
-pub fn player_collision(
-    mut commands: Commands,
-    player_query: Query<(&Transform, &HitBox), With<Player>>,
-    shuriken_query: Query<
-        (Entity, &Transform, &HitBox),
-        (With<Shuriken>, Without<MarkDespawn>, With<Reflected>),
+/// Finds every pair of colliders whose layers interact and that touched during this step, and
+/// sends the matching collision event
+///
+/// Events go out in the order the contacts happen during the step, so a projectile reaches its
+/// closest target first.
+pub fn detect_collisions(
+    spatial_grid: Res<SpatialGrid>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &HitBox,
+            &CollisionLayers,
+            Option<&PreviousPosition>,
+        ),
+        Without<MarkDespawn>,
     >,
+    mut projectile_hit_enemy_events: EventWriter<ProjectileHitEnemyEvent>,
+    mut projectile_hit_player_events: EventWriter<ProjectileHitPlayerEvent>,
+    mut player_touched_pickup_events: EventWriter<PlayerTouchedPickupEvent>,
+    mut enemy_touched_player_events: EventWriter<EnemyTouchedPlayerEvent>,
 ) {
-    for (shuriken_entity, shuriken_transform, shuriken_hitbox) in shuriken_query.iter() {
-        let shurkien_scale = shuriken_transform.scale.xy();
+    let mut contacts = Vec::new();
+
+    for (entity, transform, hitbox, layers, previous_position) in query.iter() {
+        let position = transform.translation.truncate();
+        let start = previous_position.map_or(position, |previous| previous.0);
+        let size = hitbox.0 * transform.scale.xy();
+        let (path_center, path_size) = swept_bounds(start, position, size);
 
-        for (player_transform, player_hitbox) in player_query.iter() {
-            let player_scale = player_transform.scale.xy();
+        for candidate in spatial_grid.query(path_center, path_size) {
+            // every pair is found from both sides, only the first one is kept
+            if candidate <= entity {
+                continue;
+            }
+
+            let (_, other_transform, other_hitbox, other_layers, other_previous_position) =
+                match query.get(candidate) {
+                    Ok(other) => other,
+                    Err(_) => continue,
+                };
 
-            let player_collision = collide(
-                shuriken_transform.translation,
-                shuriken_hitbox.0 * shurkien_scale,
-                player_transform.translation,
-                player_hitbox.0 * player_scale,
+            if !layers.interacts_with(other_layers) {
+                continue;
+            }
+
+            let other_position = other_transform.translation.truncate();
+            let other_start = other_previous_position.map_or(other_position, |previous| previous.0);
+
+            // sweep the first collider relative to the second one so both of their steps count
+            let time = swept_collide(
+                start + other_position - other_start,
+                position,
+                size,
+                other_position,
+                other_hitbox.0 * other_transform.scale.xy(),
             );
 
-            if player_collision.is_some() {
-                commands.entity(shuriken_entity).insert(MarkDespawn);
+            if let Some(time) = time {
+                contacts.push((
+                    time,
+                    (entity, layers.layer),
+                    (candidate, other_layers.layer),
+                ));
+            }
+        }
+    }
+
+    contacts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (_, first, second) in contacts {
+        // put the pair in a fixed order so each kind of contact only needs one arm
+        let ((a, a_layer), (b, b_layer)) = if first.1.bit() < second.1.bit() {
+            (first, second)
+        } else {
+            (second, first)
+        };
+
+        match (a_layer, b_layer) {
+            (CollisionLayer::Player, CollisionLayer::Enemy) => {
+                enemy_touched_player_events.send(EnemyTouchedPlayerEvent {
+                    enemy: b,
+                    player: a,
+                });
+            }
+            (CollisionLayer::Enemy, CollisionLayer::PlayerProjectile) => {
+                projectile_hit_enemy_events.send(ProjectileHitEnemyEvent {
+                    projectile: b,
+                    enemy: a,
+                });
             }
+            (CollisionLayer::Player, CollisionLayer::EnemyProjectile) => {
+                projectile_hit_player_events.send(ProjectileHitPlayerEvent {
+                    projectile: b,
+                    player: a,
+                });
+            }
+            (CollisionLayer::Player, CollisionLayer::Pickup) => {
+                player_touched_pickup_events.send(PlayerTouchedPickupEvent {
+                    player: a,
+                    pickup: b,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reflected shurikens stop hitting enemies and go after the players instead
+pub fn reflected_projectile_layers(mut query: Query<&mut CollisionLayers, Added<Reflected>>) {
+    for mut layers in query.iter_mut() {
+        *layers = CollisionLayers::new(CollisionLayer::EnemyProjectile, &[CollisionLayer::Player]);
+    }
+}
+
+/// Enemies with `ContactDamage` hurt the player once when touching them
+pub fn enemy_contact_collision(
+    mut commands: Commands,
+    mut enemy_touched_player_events: EventReader<EnemyTouchedPlayerEvent>,
+    player_query: Query<(), (With<Player>, Without<Invulnerable>)>,
+    enemy_query: Query<&Enemy, With<ContactDamage>>,
+    mut player_hit_events: EventWriter<PlayerHitEvent>,
+) {
+    // the damage is only removed once commands run, so keep track of who already hit this frame
+    let mut spent = Vec::new();
+
+    for event in enemy_touched_player_events.iter() {
+        if spent.contains(&event.enemy) || !player_query.contains(event.player) {
+            continue;
+        }
+
+        match enemy_query.get(event.enemy) {
+            Ok(enemy) if enemy.0 != EnemyState::Dead => (),
+            _ => continue,
+        }
+
+        spent.push(event.enemy);
+        commands.entity(event.enemy).remove::<ContactDamage>();
+        player_hit_events.send(PlayerHitEvent {
+            entity: event.player,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Vec2 = Vec2::new(10.0, 10.0);
+
+    fn assert_time(time: Option<f32>, expected: f32) {
+        match time {
+            Some(time) => assert!((time - expected).abs() < 1e-5, "{} != {}", time, expected),
+            None => panic!("expected a contact at {}", expected),
         }
     }
+
+    #[test]
+    fn enemies_touching_players_send_a_contact_event() {
+        let mut app = App::new();
+        app.init_resource::<SpatialGrid>()
+            .add_event::<ProjectileHitEnemyEvent>()
+            .add_event::<ProjectileHitPlayerEvent>()
+            .add_event::<PlayerTouchedPickupEvent>()
+            .add_event::<EnemyTouchedPlayerEvent>()
+            .add_system(update_spatial_grid)
+            .add_system(detect_collisions.after(update_spatial_grid));
+
+        let player = app
+            .world
+            .spawn((
+                Transform::default(),
+                HitBox(SIZE),
+                CollisionLayers::new(CollisionLayer::Player, &[CollisionLayer::Enemy]),
+            ))
+            .id();
+        let enemy = app
+            .world
+            .spawn((
+                Transform::from_xyz(5.0, 0.0, 0.0),
+                HitBox(SIZE),
+                CollisionLayers::new(CollisionLayer::Enemy, &[CollisionLayer::Player]),
+            ))
+            .id();
+        // out of reach
+        app.world.spawn((
+            Transform::from_xyz(100.0, 0.0, 0.0),
+            HitBox(SIZE),
+            CollisionLayers::new(CollisionLayer::Enemy, &[CollisionLayer::Player]),
+        ));
+        app.update();
+
+        let events = app.world.resource::<Events<EnemyTouchedPlayerEvent>>();
+        let contacts = events
+            .get_reader()
+            .iter(events)
+            .map(|event| (event.enemy, event.player))
+            .collect::<Vec<_>>();
+        assert_eq!(contacts, vec![(enemy, player)]);
+    }
+
+    #[test]
+    fn swept_bounds_cover_the_whole_step() {
+        let (center, size) = swept_bounds(Vec2::ZERO, Vec2::new(10.0, -20.0), Vec2::new(4.0, 4.0));
+
+        assert_eq!(center, Vec2::new(5.0, -10.0));
+        assert_eq!(size, Vec2::new(14.0, 24.0));
+    }
+
+    #[test]
+    fn large_steps_do_not_tunnel() {
+        let time = swept_collide(
+            Vec2::new(-1000.0, 0.0),
+            Vec2::new(1000.0, 0.0),
+            Vec2::new(4.0, 4.0),
+            Vec2::ZERO,
+            SIZE,
+        );
+
+        assert_time(time, 993.0 / 2000.0);
+    }
+
+    #[test]
+    fn overlapping_start_touches_straight_away() {
+        let time = swept_collide(Vec2::ONE, Vec2::new(50.0, 1.0), SIZE, Vec2::ZERO, SIZE);
+
+        assert_eq!(time, Some(0.0));
+    }
+
+    #[test]
+    fn target_behind_the_start_is_missed() {
+        let time = swept_collide(
+            Vec2::ZERO,
+            Vec2::new(100.0, 0.0),
+            SIZE,
+            Vec2::new(-50.0, 0.0),
+            SIZE,
+        );
+
+        assert_eq!(time, None);
+    }
+
+    #[test]
+    fn grazing_an_edge_is_not_a_contact() {
+        // sliding along the top edge
+        let along_edge = swept_collide(
+            Vec2::new(-100.0, 10.0),
+            Vec2::new(100.0, 10.0),
+            SIZE,
+            Vec2::ZERO,
+            SIZE,
+        );
+        // stopping right where the boxes would touch
+        let stopping_short = swept_collide(
+            Vec2::new(-100.0, 0.0),
+            Vec2::new(-10.0, 0.0),
+            SIZE,
+            Vec2::ZERO,
+            SIZE,
+        );
+
+        assert_eq!(along_edge, None);
+        assert_eq!(stopping_short, None);
+    }
+
+    /// Sweeps `a` relative to `b` the same way `detect_collisions` does
+    fn relative_collide(a_start: Vec2, a_end: Vec2, b_start: Vec2, b_end: Vec2) -> Option<f32> {
+        swept_collide(a_start + b_end - b_start, a_end, SIZE, b_end, SIZE)
+    }
+
+    #[test]
+    fn boxes_moving_through_each_other_meet_halfway() {
+        let time = relative_collide(
+            Vec2::new(-100.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            Vec2::new(-100.0, 0.0),
+        );
+
+        assert_time(time, 0.475);
+    }
+
+    #[test]
+    fn boxes_moving_together_never_meet() {
+        // checking against where the second box started would report a hit
+        let time = relative_collide(
+            Vec2::ZERO,
+            Vec2::new(100.0, 0.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(120.0, 0.0),
+        );
+
+        assert_eq!(time, None);
+    }
 }
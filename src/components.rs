@@ -9,7 +9,7 @@ pub enum EnemyState {
     Dead,
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum PlayerAction {
     Idle,
     WalkingLeft,
@@ -19,6 +19,7 @@ pub enum PlayerAction {
     Landing,
     Landed,
     Attacking,
+    Slashing,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -42,6 +43,14 @@ pub struct MarkDespawn;
 #[derive(Component)]
 pub struct Player(pub PlayerAction, pub LevelState);
 
+/// Player that threw a shuriken or swung a slash
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Owner(pub Entity);
+
+/// Where a projectile was before its last step, so collisions can check the whole path it covered
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PreviousPosition(pub Vec2);
+
 #[derive(Component)]
 pub struct Enemy(pub EnemyState);
 
@@ -54,6 +63,9 @@ pub struct Roof;
 #[derive(Component)]
 pub struct Shuriken;
 
+#[derive(Component)]
+pub struct MeleeSlash;
+
 #[derive(Component)]
 pub struct Background;
 
@@ -75,6 +87,28 @@ pub struct Gravity(pub f32);
 #[derive(Component)]
 pub struct InitialEnemySpeed(pub f32);
 
+#[derive(Component, Debug)]
+pub struct Health(pub u32);
+
+impl Health {
+    /// Removes `amount` health and returns whether nothing is left
+    pub fn damage(&mut self, amount: u32) -> bool {
+        self.0 = self.0.saturating_sub(amount);
+        self.0 == 0
+    }
+}
+
+/// Keeps a hit sprite tinted white until `timer` is over, then puts `original_color` back
+#[derive(Component)]
+pub struct HitFlashTimer {
+    pub timer: Timer,
+    pub original_color: Color,
+}
+
+/// Lets enemy attacks pass through the player
+#[derive(Component)]
+pub struct Invulnerable;
+
 #[derive(Component, Deref, DerefMut)]
 pub struct Velocity(pub Vec2);
 
@@ -87,6 +121,9 @@ pub struct WalkingAnimationTimer(pub Timer);
 #[derive(Component, Deref, DerefMut)]
 pub struct FlippingAnimationTimer(pub Timer);
 
+#[derive(Component, Deref, DerefMut)]
+pub struct SlashingAnimationTimer(pub Timer);
+
 #[derive(Component)]
 pub struct Effect;
 
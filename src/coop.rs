@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::prelude::*;
+
+pub const COOP_LIVES: u32 = 3;
+/// Horizontal gap between the two players where they spawn and land
+pub const COOP_PLAYER_SPACING: f32 = 40.0;
+/// Tint that tells the second player apart from the first
+pub const PLAYER_TWO_TINT: Color = Color::rgb(0.6, 0.8, 1.0);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PlayerSlot {
+    One,
+    Two,
+}
+
+/// Keys a player uses in the bonus stage
+pub struct PlayerControls {
+    pub left: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub up: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+    /// Held together with a horizontal key to throw diagonally
+    pub aim_down: Vec<KeyCode>,
+    pub attack: Vec<KeyCode>,
+    pub melee: Vec<KeyCode>,
+    pub flip: Vec<KeyCode>,
+    pub ninjutsu: Vec<KeyCode>,
+}
+
+impl PlayerSlot {
+    /// Player one keeps the single player keys, player two plays on the right of the keyboard
+    pub fn controls(&self) -> PlayerControls {
+        match self {
+            PlayerSlot::One => PlayerControls {
+                left: vec![KeyCode::Left, KeyCode::A],
+                right: vec![KeyCode::Right, KeyCode::D],
+                up: vec![KeyCode::W],
+                down: vec![KeyCode::S],
+                aim_down: vec![KeyCode::Down, KeyCode::S],
+                attack: vec![KeyCode::Down, KeyCode::X],
+                melee: vec![KeyCode::Z],
+                flip: vec![KeyCode::C],
+                ninjutsu: vec![KeyCode::Space],
+            },
+            PlayerSlot::Two => PlayerControls {
+                left: vec![KeyCode::J],
+                right: vec![KeyCode::L],
+                up: vec![KeyCode::I],
+                down: vec![KeyCode::K],
+                aim_down: vec![KeyCode::K],
+                attack: vec![KeyCode::U],
+                melee: vec![KeyCode::O],
+                flip: vec![KeyCode::Y],
+                ninjutsu: vec![KeyCode::H],
+            },
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlayerSlot::One => "P1",
+            PlayerSlot::Two => "P2",
+        }
+    }
+
+    /// Offset from the shared spawn and landing spots so the players don't overlap
+    pub fn offset_x(&self) -> f32 {
+        match self {
+            PlayerSlot::One => 0.0,
+            PlayerSlot::Two => -COOP_PLAYER_SPACING,
+        }
+    }
+
+    pub fn tint(&self) -> Color {
+        match self {
+            PlayerSlot::One => Color::WHITE,
+            PlayerSlot::Two => PLAYER_TWO_TINT,
+        }
+    }
+}
+
+/// Hits a co-op player can take before dropping out
+#[derive(Component, Debug)]
+pub struct PlayerLives(pub u32);
+
+/// Kill score of each player, kept apart from the shared `Score` so it survives a player
+/// dropping out
+#[derive(Resource, Default, Debug)]
+pub struct CoopScores(pub HashMap<PlayerSlot, u32>);
+
+#[derive(Component)]
+struct OnCoopResultsScreen;
+
+pub struct CoopPlugin;
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CoopScores>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(start_coop_run))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(credit_kills)
+                    .with_system(coop_lives),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::EndStage).with_system(coop_results_setup),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage)
+                    .with_system(despawner::<OnCoopResultsScreen>),
+            );
+    }
+}
+
+pub fn start_coop_run(
+    game_mode: Res<GameMode>,
+    mut score: ResMut<Score>,
+    mut coop_scores: ResMut<CoopScores>,
+) {
+    if *game_mode != GameMode::Coop {
+        return;
+    }
+
+    score.0 = 0;
+    coop_scores.0.clear();
+}
+
+/// Gives the kill score to whoever landed the killing blow
+pub fn credit_kills(
+    mut coop_scores: ResMut<CoopScores>,
+    player_query: Query<&PlayerSlot>,
+    mut enemy_killed_events: EventReader<EnemyKilledEvent>,
+) {
+    for event in enemy_killed_events.iter() {
+        let slot = match event.killer.map(|killer| player_query.get(killer)) {
+            Some(Ok(slot)) => slot,
+            _ => continue,
+        };
+
+        *coop_scores.0.entry(*slot).or_default() += ENEMY_KILL_SCORE;
+    }
+}
+
+/// Takes a life for every hit, players without lives drop out and the run ends once both are out
+pub fn coop_lives(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut game_state: ResMut<State<GameState>>,
+    mut player_query: Query<(Entity, &Transform, &mut PlayerLives), Without<MarkDespawn>>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+) {
+    for event in player_hit_events.iter() {
+        let (entity, transform, mut lives) = match player_query.get_mut(event.entity) {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+
+        lives.0 = lives.0.saturating_sub(1);
+        if lives.0 > 0 {
+            continue;
+        }
+
+        commands.entity(entity).insert(MarkDespawn);
+        commands.spawn(DeathEffectBundle::new(&game_assets, transform.translation));
+
+        let players_left = player_query
+            .iter()
+            .filter(|(other, _, lives)| *other != entity && lives.0 > 0)
+            .count();
+        if players_left == 0 {
+            end_stage(&mut game_state);
+            return;
+        }
+    }
+}
+
+/// Shows each player's kills next to the combined score and who came out ahead
+pub fn coop_results_setup(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    game_assets: Res<GameAssets>,
+    score: Res<Score>,
+    coop_scores: Res<CoopScores>,
+) {
+    if *game_mode != GameMode::Coop {
+        return;
+    }
+
+    let player_one = coop_scores.0.get(&PlayerSlot::One).copied().unwrap_or(0);
+    let player_two = coop_scores.0.get(&PlayerSlot::Two).copied().unwrap_or(0);
+    let verdict = match player_one.cmp(&player_two) {
+        std::cmp::Ordering::Greater => "P1 WINS",
+        std::cmp::Ordering::Less => "P2 WINS",
+        std::cmp::Ordering::Equal => "DRAW",
+    };
+
+    commands
+        .spawn((results_screen(), OnCoopResultsScreen))
+        .with_children(|parent| {
+            parent.spawn(results_text(format!("P1 {}", player_one), &game_assets));
+            parent.spawn(results_text(format!("P2 {}", player_two), &game_assets));
+            parent.spawn(results_text(format!("TEAM {}", score.0), &game_assets));
+            parent.spawn(results_text(verdict.to_string(), &game_assets));
+        });
+}
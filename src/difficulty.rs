@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use crate::prelude::*;
+use ron::de::from_bytes;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Difficulty {
+    #[default]
+    Easy,
+    Medium,
+    Hard,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DifficultySettings {
+    pub ninjutsu_charges: u32,
+}
+
+#[derive(Resource, Debug)]
+pub struct DifficultyConfig {
+    pub current: Difficulty,
+    settings: HashMap<Difficulty, DifficultySettings>,
+}
+
+impl Difficulty {
+    /// The next difficulty, wrapping back around to the easiest one
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Medium,
+            Difficulty::Medium => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+}
+
+impl DifficultyConfig {
+    /// Settings for the currently selected difficulty
+    pub fn settings(&self) -> &DifficultySettings {
+        &self.settings[&self.current]
+    }
+}
+
+pub struct DifficultyPlugin;
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        let settings: HashMap<Difficulty, DifficultySettings> =
+            from_bytes(include_bytes!("../data/difficulty.ron")).unwrap();
+
+        app.insert_resource(DifficultyConfig {
+            current: Difficulty::default(),
+            settings,
+        });
+    }
+}
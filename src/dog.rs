@@ -0,0 +1,352 @@
+use crate::prelude::*;
+
+/// Seconds the sic input has to be held before Yamato is sent
+pub const DOG_CHARGE_SEC: f32 = 0.5;
+pub const DOG_COOLDOWN_SEC: f32 = 4.0;
+pub const DOG_STUN_SEC: f32 = 2.5;
+pub const DOG_SPEED: f32 = 260.0;
+pub const DOG_JUMP_SPEED: f32 = 480.0;
+/// Enemies further away than this are ignored by the sic command
+pub const DOG_SIC_RANGE: f32 = 400.0;
+/// How far behind the player Yamato trails when following
+pub const DOG_FOLLOW_OFFSET: f32 = 36.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DogState {
+    Following,
+    Running(Entity),
+    /// Holding on to an enemy until the timer finishes
+    Latched(Entity),
+}
+
+/// Yamato, the dog companion of the platforming stages
+#[derive(Component, Debug)]
+pub struct Dog {
+    pub state: DogState,
+    pub charge: Timer,
+    pub cooldown: Timer,
+    pub latch: Timer,
+}
+
+impl Default for Dog {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(DOG_COOLDOWN_SEC, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+
+        Dog {
+            state: DogState::Following,
+            charge: Timer::from_seconds(DOG_CHARGE_SEC, TimerMode::Once),
+            cooldown,
+            latch: Timer::from_seconds(DOG_STUN_SEC, TimerMode::Once),
+        }
+    }
+}
+
+/// Stunned enemies stop moving and can't block shurikens
+#[derive(Component, Deref, DerefMut)]
+pub struct Stunned(pub Timer);
+
+#[derive(Bundle)]
+pub struct DogBundle {
+    dog: Dog,
+    walking_animation_timer: WalkingAnimationTimer,
+    gravity: PlatformGravity,
+    velocity: Velocity,
+    hitbox: HitBox,
+    sprite_bundle: SpriteSheetBundle,
+}
+
+impl DogBundle {
+    pub fn new(game_assets: &Res<GameAssets>, position: Vec2) -> Self {
+        DogBundle {
+            dog: Dog::default(),
+            walking_animation_timer: WalkingAnimationTimer(Timer::from_seconds(
+                0.1,
+                TimerMode::Repeating,
+            )),
+            gravity: PlatformGravity(PLATFORM_GRAVITY),
+            velocity: Velocity(Vec2::ZERO),
+            hitbox: HitBox(Vec2::new(30.0, 20.0)),
+            sprite_bundle: SpriteSheetBundle {
+                texture_atlas: game_assets.yamato.clone(),
+                transform: Transform::from_translation(position.extend(1.8)),
+                ..default()
+            },
+        }
+    }
+}
+
+pub struct DogPlugin;
+impl Plugin for DogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Platforming)
+                .with_system(spawn_dog)
+                .with_system(dog_sic_command)
+                .with_system(dog_movement.after(dog_sic_command).after(platform_physics))
+                .with_system(dog_latch.after(dog_movement))
+                .with_system(dog_animation.after(dog_movement))
+                .with_system(stun_timer),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::EndStage).with_system(despawner::<Dog>));
+    }
+}
+
+/// Spawns Yamato next to the player once the stage has been laid out
+fn spawn_dog(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    player_query: Query<&Transform, Added<PlatformPlayer>>,
+) {
+    for player_transform in player_query.iter() {
+        commands.spawn(DogBundle::new(
+            &game_assets,
+            player_transform.translation.truncate() - Vec2::new(DOG_FOLLOW_OFFSET, 0.0),
+        ));
+    }
+}
+
+/// Holding S or left shift charges the sic command, letting go once charged sends Yamato after
+/// the nearest enemy
+pub fn dog_sic_command(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    player_query: Query<(&Transform, &PlatformPlayer)>,
+    enemy_query: Query<(Entity, &Transform), (With<GroundEnemy>, Without<MarkDespawn>)>,
+    mut dog_query: Query<(&mut Dog, &Transform)>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for (mut dog, dog_transform) in dog_query.iter_mut() {
+        dog.cooldown.tick(time.delta());
+
+        let player_position = match nearest_platform_player(
+            player_query.iter(),
+            dog_transform.translation.truncate(),
+        ) {
+            Some((player_transform, _)) => player_transform.translation.truncate(),
+            None => continue,
+        };
+
+        if dog.state != DogState::Following || !dog.cooldown.finished() {
+            dog.charge.reset();
+            continue;
+        }
+
+        if keyboard_input.any_pressed(vec![KeyCode::S, KeyCode::LShift]) {
+            dog.charge.tick(time.delta());
+            continue;
+        }
+
+        let charged = dog.charge.finished();
+        dog.charge.reset();
+        if !charged {
+            continue;
+        }
+
+        let target = enemy_query
+            .iter()
+            .map(|(entity, transform)| {
+                let distance = transform.translation.truncate().distance(player_position);
+                (entity, distance)
+            })
+            .filter(|(_, distance)| *distance <= DOG_SIC_RANGE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        if let Some((target, _)) = target {
+            dog.state = DogState::Running(target);
+            dog.cooldown.reset();
+            sfx_events.send(SFXEvents::MeleeAttackSound);
+        }
+    }
+}
+
+/// The player Yamato stays with when more than one is around
+fn nearest_platform_player<'a>(
+    players: impl Iterator<Item = (&'a Transform, &'a PlatformPlayer)>,
+    position: Vec2,
+) -> Option<(&'a Transform, &'a PlatformPlayer)> {
+    players.min_by(|(a, _), (b, _)| {
+        let a = a.translation.truncate().distance(position);
+        let b = b.translation.truncate().distance(position);
+        a.total_cmp(&b)
+    })
+}
+
+/// Whether any solid collider contains `point`
+fn solid_at(
+    point: Vec2,
+    solid_query: &Query<(&Transform, &HitBox), (With<Solid>, Without<Dog>)>,
+) -> bool {
+    solid_query.iter().any(|(transform, hitbox)| {
+        let offset = (point - transform.translation.truncate()).abs();
+        offset.x <= hitbox.0.x / 2.0 && offset.y <= hitbox.0.y / 2.0
+    })
+}
+
+/// Runs along the ground colliders towards the player or the sic target, jumping over gaps and
+/// up walls on the way
+pub fn dog_movement(
+    player_query: Query<(&Transform, &PlatformPlayer), Without<Dog>>,
+    target_query: Query<&Transform, (With<GroundEnemy>, Without<MarkDespawn>, Without<Dog>)>,
+    solid_query: Query<(&Transform, &HitBox), (With<Solid>, Without<Dog>)>,
+    mut dog_query: Query<(&mut Dog, &mut Transform, &mut Velocity, &HitBox)>,
+) {
+    for (mut dog, mut transform, mut velocity, hitbox) in dog_query.iter_mut() {
+        let position = transform.translation.truncate();
+
+        let (player_transform, platform_player) =
+            match nearest_platform_player(player_query.iter(), position) {
+                Some(player) => player,
+                None => continue,
+            };
+        let player_position = player_transform.translation.truncate();
+
+        let destination = match dog.state {
+            DogState::Following => {
+                player_position - Vec2::new(platform_player.facing * DOG_FOLLOW_OFFSET, 0.0)
+            }
+            DogState::Running(target) => match target_query.get(target) {
+                Ok(target_transform) => target_transform.translation.truncate(),
+                Err(_) => {
+                    dog.state = DogState::Following;
+                    continue;
+                }
+            },
+            DogState::Latched(_) => continue,
+        };
+
+        // catching up after falling behind or off the level
+        if dog.state == DogState::Following
+            && position.distance(player_position) > PLATFORM_CAMERA_HALF_WIDTH * 2.0
+        {
+            transform.translation = destination.extend(transform.translation.z);
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+
+        let offset = destination.x - position.x;
+        if offset.abs() < hitbox.0.x / 2.0 {
+            velocity.x = 0.0;
+            continue;
+        }
+
+        let direction = offset.signum();
+        velocity.x = direction * DOG_SPEED;
+
+        // platform_physics zeroes the vertical speed whenever a body lands
+        if velocity.y != 0.0 {
+            continue;
+        }
+
+        let front = position.x + direction * (hitbox.0.x / 2.0 + 4.0);
+        let wall_ahead = solid_at(Vec2::new(front, position.y), &solid_query);
+        let gap_ahead = !solid_at(
+            Vec2::new(front, position.y - hitbox.0.y / 2.0 - 4.0),
+            &solid_query,
+        );
+        let target_above = destination.y - position.y > hitbox.0.y * 2.0;
+
+        if wall_ahead || gap_ahead || (target_above && offset.abs() < DOG_SIC_RANGE / 4.0) {
+            velocity.y = DOG_JUMP_SPEED;
+        }
+    }
+}
+
+/// Latches on to the target once it is reached, stunning it until Yamato lets go
+pub fn dog_latch(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut target_query: Query<
+        (&Transform, &HitBox, &mut TextureAtlasSprite),
+        (With<GroundEnemy>, Without<MarkDespawn>, Without<Dog>),
+    >,
+    mut dog_query: Query<(&mut Dog, &mut Transform, &mut Velocity, &HitBox)>,
+) {
+    for (mut dog, mut transform, mut velocity, hitbox) in dog_query.iter_mut() {
+        match dog.state {
+            DogState::Running(target) => {
+                let (target_transform, target_hitbox, mut sprite) =
+                    match target_query.get_mut(target) {
+                        Ok(target) => target,
+                        Err(_) => continue,
+                    };
+
+                if overlaps(
+                    transform.translation.truncate(),
+                    hitbox.0,
+                    target_transform.translation.truncate(),
+                    target_hitbox.0,
+                ) {
+                    dog.state = DogState::Latched(target);
+                    dog.latch.reset();
+                    sprite.color = Color::rgb(0.6, 0.6, 1.0);
+                    commands
+                        .entity(target)
+                        .insert(Stunned(Timer::from_seconds(DOG_STUN_SEC, TimerMode::Once)));
+                }
+            }
+            DogState::Latched(target) => {
+                let target_transform = match target_query.get(target) {
+                    Ok((target_transform, ..)) => target_transform,
+                    Err(_) => {
+                        dog.state = DogState::Following;
+                        continue;
+                    }
+                };
+
+                transform.translation = target_transform
+                    .translation
+                    .truncate()
+                    .extend(transform.translation.z);
+                velocity.0 = Vec2::ZERO;
+
+                if dog.latch.tick(time.delta()).finished() {
+                    dog.state = DogState::Following;
+                }
+            }
+            DogState::Following => (),
+        }
+    }
+}
+
+/// Frame 0 is sitting, 1 and 2 are running and 3 is biting
+pub fn dog_animation(
+    time: Res<Time>,
+    mut query: Query<(
+        &Dog,
+        &Velocity,
+        &mut WalkingAnimationTimer,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    for (dog, velocity, mut walking_animation_timer, mut sprite) in query.iter_mut() {
+        // the sprite sheet faces right and on cooldown Yamato is drawn a little faded
+        if velocity.x != 0.0 {
+            sprite.flip_x = velocity.x < 0.0;
+        }
+        sprite
+            .color
+            .set_a(if dog.cooldown.finished() { 1.0 } else { 0.7 });
+
+        if matches!(dog.state, DogState::Latched(_)) {
+            sprite.index = 3;
+        } else if velocity.x == 0.0 {
+            sprite.index = 0;
+        } else if walking_animation_timer.tick(time.delta()).just_finished() || sprite.index == 0 {
+            sprite.index = if sprite.index == 1 { 2 } else { 1 };
+        }
+    }
+}
+
+pub fn stun_timer(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Stunned, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut stunned, mut sprite) in query.iter_mut() {
+        if stunned.tick(time.delta()).finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<Stunned>();
+        }
+    }
+}
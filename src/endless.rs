@@ -0,0 +1,289 @@
+use crate::{pause_game, prelude::*};
+use bevy::time::Stopwatch;
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use ron::de::from_bytes;
+use serde::{Deserialize, Serialize};
+
+pub const ENDLESS_SAVE_FILE: &str = "endless.ron";
+pub const ENDLESS_RECORD_COUNT: usize = 5;
+pub const ENDLESS_PAIR_SPACING: f32 = 60.0;
+/// Generated waves checked at startup, far enough for every escalation to reach its limit
+pub const ENDLESS_VALIDATED_WAVES: usize = 100;
+
+/// A value that changes by `per_wave` every wave until it reaches `limit`
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Escalation {
+    pub base: f32,
+    pub per_wave: f32,
+    pub limit: f32,
+}
+
+impl Escalation {
+    pub fn at(&self, wave_number: usize) -> f32 {
+        let value = self.base + self.per_wave * wave_number.saturating_sub(1) as f32;
+
+        if self.per_wave >= 0.0 {
+            value.min(self.limit)
+        } else {
+            value.max(self.limit)
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EndlessArchetype {
+    pub enemy_type: EnemyType,
+    /// First wave the archetype can show up in
+    pub from_wave: usize,
+    /// Relative chance of being picked among the unlocked archetypes
+    pub weight: f32,
+}
+
+#[derive(Resource, Deserialize, Clone, Debug)]
+pub struct EndlessConfig {
+    pub lives: u32,
+    pub spawn_delay_sec: f32,
+    pub enemy_count: Escalation,
+    pub enemy_interval_sec: Escalation,
+    pub speed_multiplier: Escalation,
+    pub formations_from_wave: usize,
+    pub archetypes: Vec<EndlessArchetype>,
+}
+
+impl EndlessConfig {
+    /// Builds the `wave_number`th wave of an endless run
+    pub fn wave(&self, wave_number: usize) -> WaveData {
+        let mut rng = thread_rng();
+
+        let unlocked = self
+            .archetypes
+            .iter()
+            .filter(|archetype| archetype.from_wave <= wave_number)
+            .collect::<Vec<&EndlessArchetype>>();
+        let enemy_type = unlocked
+            .choose_weighted(&mut rng, |archetype| archetype.weight)
+            .map_or(EnemyType::Pawn, |archetype| archetype.enemy_type);
+
+        let formation = if wave_number < self.formations_from_wave {
+            Formation::Single
+        } else {
+            match rng.gen_range(0..3) {
+                0 => Formation::Single,
+                1 => Formation::Pair {
+                    spacing: ENDLESS_PAIR_SPACING,
+                },
+                _ => Formation::Mirrored,
+            }
+        };
+        let enemies_per_formation = formation.positions(StartingWall::Left, 0.0).len() as f32;
+        let enemy_count = (self.enemy_count.at(wave_number) / enemies_per_formation).ceil();
+
+        // pairs stack upwards from the usual spawn height so both enemies start inside the world
+        let spawn_y = match formation {
+            Formation::Pair { spacing } => ENEMY_SPAWN_Y + spacing,
+            _ => ENEMY_SPAWN_Y,
+        };
+
+        let starting_wall = if rng.gen_bool(0.5) {
+            WallPattern::Alternating
+        } else {
+            WallPattern::Random
+        };
+
+        WaveData {
+            enemy_type,
+            enemy_count: (enemy_count as u32).max(1),
+            enemy_interval_sec: self.enemy_interval_sec.at(wave_number),
+            enemy_spawn_timer_sec: self.spawn_delay_sec,
+            starting_wall,
+            spawn_y,
+            formation,
+            overrides: EnemyOverrides {
+                speed: Some(enemy_type.get_speed() * self.speed_multiplier.at(wave_number)),
+                ..default()
+            },
+            wait_for_clear: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct EndlessRecord {
+    pub score: u32,
+    pub time_sec: f32,
+}
+
+/// Best endless runs, kept apart from the story mode score
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct EndlessRecords {
+    pub high_scores: Vec<EndlessRecord>,
+    pub longest_runs: Vec<EndlessRecord>,
+}
+
+impl EndlessRecords {
+    pub fn submit(&mut self, record: EndlessRecord) {
+        self.high_scores.push(record);
+        self.high_scores.sort_by(|a, b| b.score.cmp(&a.score));
+        self.high_scores.truncate(ENDLESS_RECORD_COUNT);
+
+        self.longest_runs.push(record);
+        self.longest_runs
+            .sort_by(|a, b| b.time_sec.total_cmp(&a.time_sec));
+        self.longest_runs.truncate(ENDLESS_RECORD_COUNT);
+    }
+}
+
+/// Time survived in the current endless run
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct EndlessClock(pub Stopwatch);
+
+#[derive(Component)]
+pub struct OnEndlessRecordsScreen;
+
+pub struct EndlessPlugin;
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        let endless_config: EndlessConfig =
+            from_bytes(include_bytes!("../data/endless.ron")).unwrap();
+
+        if !endless_config
+            .archetypes
+            .iter()
+            .any(|archetype| archetype.from_wave <= 1)
+        {
+            panic!("data/endless.ron needs an archetype available from the first wave");
+        }
+
+        for wave_number in 1..=ENDLESS_VALIDATED_WAVES {
+            if let Err(error) = endless_config.wave(wave_number).validate() {
+                panic!(
+                    "data/endless.ron generates an invalid wave {}: {}",
+                    wave_number, error
+                );
+            }
+        }
+
+        app.insert_resource(endless_config)
+            .insert_resource(load_save::<EndlessRecords>(ENDLESS_SAVE_FILE))
+            .init_resource::<EndlessClock>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(start_endless_run))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(endless_wave_generator)
+                    .with_system(endless_clock),
+            )
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(endless_lives))
+            .add_system_set(
+                SystemSet::on_enter(GameState::EndStage).with_system(endless_records_setup),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage)
+                    .with_system(despawner::<OnEndlessRecordsScreen>),
+            );
+    }
+}
+
+pub fn start_endless_run(
+    game_mode: Res<GameMode>,
+    endless_config: Res<EndlessConfig>,
+    mut level: ResMut<Level>,
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+    mut endless_clock: ResMut<EndlessClock>,
+) {
+    if *game_mode != GameMode::Endless {
+        return;
+    }
+
+    *level = Level(Vec::new(), 0);
+    score.0 = 0;
+    lives.0 = endless_config.lives;
+    endless_clock.reset();
+}
+
+/// Queues a new wave every time the previous one has been cleared
+pub fn endless_wave_generator(
+    game_mode: Res<GameMode>,
+    endless_config: Res<EndlessConfig>,
+    mut level: ResMut<Level>,
+) {
+    if *game_mode != GameMode::Endless || level.1 < level.0.len() {
+        return;
+    }
+
+    let wave_number = level.0.len() + 1;
+    level.0.push(Wave::from(&endless_config.wave(wave_number)));
+}
+
+pub fn endless_clock(
+    time: Res<Time>,
+    game_mode: Res<GameMode>,
+    mut endless_clock: ResMut<EndlessClock>,
+) {
+    if *game_mode == GameMode::Endless {
+        endless_clock.tick(time.delta());
+    }
+}
+
+/// Takes a life for every hit and ends the run once none are left
+pub fn endless_lives(
+    game_mode: Res<GameMode>,
+    score: Res<Score>,
+    endless_clock: Res<EndlessClock>,
+    mut lives: ResMut<Lives>,
+    mut endless_records: ResMut<EndlessRecords>,
+    mut game_state: ResMut<State<GameState>>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+) {
+    if *game_mode != GameMode::Endless {
+        player_hit_events.clear();
+        return;
+    }
+
+    for _ in player_hit_events.iter() {
+        if lives.0 == 0 {
+            break;
+        }
+
+        lives.0 -= 1;
+
+        if lives.0 == 0 {
+            endless_records.submit(EndlessRecord {
+                score: score.0,
+                time_sec: endless_clock.elapsed_secs(),
+            });
+            write_save(ENDLESS_SAVE_FILE, &*endless_records);
+
+            end_stage(&mut game_state);
+        }
+    }
+}
+
+pub fn endless_records_setup(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    game_assets: Res<GameAssets>,
+    endless_records: Res<EndlessRecords>,
+) {
+    if *game_mode != GameMode::Endless {
+        return;
+    }
+
+    commands
+        .spawn((results_screen(), OnEndlessRecordsScreen))
+        .with_children(|parent| {
+            parent.spawn(results_text("BEST SCORES".to_string(), &game_assets));
+            for record in endless_records.high_scores.iter() {
+                parent.spawn(results_text(format!("{}", record.score), &game_assets));
+            }
+
+            parent.spawn(results_text("LONGEST RUNS".to_string(), &game_assets));
+            for record in endless_records.longest_runs.iter() {
+                parent.spawn(results_text(
+                    format!("{:.1}s", record.time_sec),
+                    &game_assets,
+                ));
+            }
+        });
+}
@@ -1,5 +1,8 @@
+use std::collections::HashMap;
+
 use crate::{assets::GameAssets, pause_game, prelude::*};
 use rand::{thread_rng, Rng};
+use ron::de::from_bytes;
 use serde::Deserialize;
 
 #[derive(Clone, Debug)]
@@ -11,14 +14,161 @@ pub struct SpawnInterval(pub Timer);
 #[derive(Component, Debug)]
 pub struct ReflectChance(pub f32);
 
+pub const ENEMY_KILL_SCORE: u32 = 100;
+pub const ENEMY_KNOCKBACK_DISTANCE: f32 = 12.0;
+pub const HIT_FLASH_SEC: f32 = 0.2;
+/// Bright enough that every lit channel of the texture saturates to white
+pub const HIT_FLASH_COLOR: Color = Color::rgb(8.0, 8.0, 8.0);
+pub const SHIELD_COVER_ANGLE_DEG: f32 = 30.0;
+pub const ENEMY_SPAWN_Y: f32 = -275.0;
+
+/// Blocks every shuriken coming from above within `SHIELD_COVER_ANGLE_DEG` of straight down
+#[derive(Component, Debug)]
+pub struct Shield;
+
+impl Shield {
+    pub fn covers(&self, projectile_velocity: Vec2) -> bool {
+        projectile_velocity.y < 0.0
+            && projectile_velocity.angle_between(Vec2::NEG_Y).abs()
+                < SHIELD_COVER_ANGLE_DEG.to_radians()
+    }
+}
+
+/// Shows the shield block frames for as long as the timer runs
+#[derive(Component, Deref, DerefMut)]
+pub struct BlockingTimer(pub Timer);
+
+/// Makes an enemy leap from walls straight at the player and curve after them mid-air
+#[derive(Component, Debug)]
+pub struct Diver {
+    /// Radians per second the enemy can turn while diving
+    pub turn_rate: f32,
+    pub dive_timer: Timer,
+}
+
+impl Diver {
+    pub fn new(settings: DiveSettings) -> Self {
+        let mut dive_timer = Timer::from_seconds(settings.dive_sec, TimerMode::Once);
+        dive_timer.tick(dive_timer.duration());
+
+        Diver {
+            turn_rate: settings.turn_rate_deg.to_radians(),
+            dive_timer,
+        }
+    }
+}
+
+/// Enemies that hurt the player by touching them
+#[derive(Component, Debug)]
+pub struct ContactDamage;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DiveSettings {
+    pub turn_rate_deg: f32,
+    pub dive_sec: f32,
+}
+
+/// Tunables of an enemy archetype loaded from `data/enemies.ron`
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EnemySettings {
+    pub health: u32,
+    /// Overrides the random time an enemy hangs on a wall before leaping
+    pub wall_hang_sec: Option<f32>,
+    pub projectile: Option<ProjectileSettings>,
+    pub dive: Option<DiveSettings>,
+}
+
+impl Default for EnemySettings {
+    fn default() -> Self {
+        EnemySettings {
+            health: 1,
+            wall_hang_sec: None,
+            projectile: None,
+            dive: None,
+        }
+    }
+}
+
+/// Per-spawn tweaks layered over the defaults of an `EnemyType`
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct EnemyOverrides {
+    pub speed: Option<f32>,
+    /// x and y values normalized between 0 and 1
+    pub trajectory: Option<(f32, f32)>,
+    pub path: Option<PathSettings>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PathSettings {
+    /// World positions visited in order, mirrored horizontally for enemies leaving the right wall
+    pub waypoints: Vec<(f32, f32)>,
+    pub speed: f32,
+}
+
+/// Steers an enemy through fixed points before it falls back to its regular movement
+#[derive(Component, Debug)]
+pub struct ScriptedPath {
+    pub waypoints: Vec<Vec2>,
+    pub next: usize,
+    pub speed: f32,
+}
+
+impl ScriptedPath {
+    pub fn new(settings: &PathSettings, starting_wall: StartingWall) -> Self {
+        let mirror = match starting_wall {
+            StartingWall::Left => 1.0,
+            StartingWall::Right => -1.0,
+        };
+
+        ScriptedPath {
+            waypoints: settings
+                .waypoints
+                .iter()
+                .map(|(x, y)| Vec2::new(x * mirror, *y))
+                .collect(),
+            next: 0,
+            speed: settings.speed,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct EnemyConfig(pub HashMap<EnemyType, EnemySettings>);
+
+impl EnemyConfig {
+    pub fn settings(&self, enemy_type: EnemyType) -> EnemySettings {
+        self.0.get(&enemy_type).cloned().unwrap_or_default()
+    }
+}
+
+/// Sent when an enemy takes damage without dying
+pub struct EnemyHitEvent {
+    pub entity: Entity,
+    /// Direction the enemy gets pushed in
+    pub knockback: Vec2,
+}
+
+/// Sent once an enemy has been marked as `EnemyState::Dead` by any attack
+pub struct EnemyKilledEvent {
+    pub entity: Entity,
+    pub enemy_type: EnemyType,
+    pub position: Vec3,
+    /// Player credited with the kill
+    pub killer: Option<Entity>,
+}
+
 #[derive(Bundle)]
 pub struct EnemyBundle {
     enemy: Enemy,
+    enemy_type: EnemyType,
     velocity: Velocity,
     gravity: Gravity,
     initial_enemy_speed: InitialEnemySpeed,
     wall_hanging_timer: WallHangingTimer,
     hitbox: HitBox,
+    collision_layers: CollisionLayers,
     sprite_bundle: SpriteSheetBundle,
     reflect_chance: ReflectChance,
     dimensions: Dimensions,
@@ -33,14 +183,20 @@ impl EnemyBundle {
     /// * `enemy_speed` - Starting speed of the enemy
     /// * `initial_enemy_speed` - The force used to calculate the speed of an enemy when changing direction
     /// * `trajectory` - Starting trajectory of the enemy used to calculate launch angle of the enemy; x and y values normalized between 0 and 1
+    /// * `spawn_y` - Height on the starting wall the enemy leaps from
+    /// * `overrides` - Speed and trajectory replacing the defaults of `enemy_type`
     ///
     pub fn new(
         enemy_type: EnemyType,
-        game_assets: Res<GameAssets>,
+        game_assets: &Res<GameAssets>,
         starting_wall: StartingWall,
+        spawn_y: f32,
+        overrides: &EnemyOverrides,
     ) -> Result<Self, String> {
-        let trajectory = enemy_type.get_trajectory();
-        let enemy_speed = enemy_type.get_speed();
+        let trajectory = overrides
+            .trajectory
+            .map_or(enemy_type.get_trajectory(), |(x, y)| Vec2::new(x, y));
+        let enemy_speed = overrides.speed.unwrap_or(enemy_type.get_speed());
         let reflect_chance = enemy_type.get_reflect_chance();
 
         if (trajectory.x, trajectory.y) < (0.0, 0.0) || (trajectory.x, trajectory.y) > (1.0, 1.0) {
@@ -62,6 +218,7 @@ impl EnemyBundle {
 
         Ok(EnemyBundle {
             enemy: Enemy(EnemyState::Airborne),
+            enemy_type,
             velocity: Velocity(Vec2::new(
                 trajectory.x * enemy_speed * direction,
                 trajectory.y * enemy_speed,
@@ -71,7 +228,7 @@ impl EnemyBundle {
             sprite_bundle: SpriteSheetBundle {
                 texture_atlas: enemy_type.get_texture(game_assets),
                 transform: Transform {
-                    translation: Vec3::new(starting_x, -275.0, 1.0),
+                    translation: Vec3::new(starting_x, spawn_y, 1.0),
                     ..default()
                 },
                 ..default()
@@ -81,60 +238,143 @@ impl EnemyBundle {
                 TimerMode::Repeating,
             )),
             hitbox: HitBox(Vec2::new(35.0, 60.0)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Enemy,
+                &[CollisionLayer::PlayerProjectile, CollisionLayer::Player],
+            ),
             reflect_chance: ReflectChance(reflect_chance),
             dimensions: Dimensions(Vec2::new(30.0, 30.0)),
         })
     }
 }
 
-#[derive(Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+/// Spawns an enemy along with the components specific to its archetype
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    enemy_type: EnemyType,
+    game_assets: &Res<GameAssets>,
+    starting_wall: StartingWall,
+    spawn_y: f32,
+    overrides: &EnemyOverrides,
+    enemy_config: &EnemyConfig,
+) -> Result<Entity, String> {
+    let settings = enemy_config.settings(enemy_type);
+    let mut enemy = commands.spawn(EnemyBundle::new(
+        enemy_type,
+        game_assets,
+        starting_wall,
+        spawn_y,
+        overrides,
+    )?);
+    enemy.insert(Health(settings.health.max(1)));
+
+    if let Some(path) = &overrides.path {
+        enemy.insert(ScriptedPath::new(path, starting_wall));
+    }
+
+    if let Some(wall_hang_sec) = settings.wall_hang_sec {
+        enemy.insert(WallHangingTimer(Timer::from_seconds(
+            wall_hang_sec,
+            TimerMode::Repeating,
+        )));
+    }
+
+    if let Some(projectile) = settings.projectile {
+        enemy.insert(ProjectileThrower::new(projectile));
+    }
+
+    if let Some(dive) = settings.dive {
+        enemy.insert((Diver::new(dive), ContactDamage));
+    }
+
+    match enemy_type {
+        EnemyType::Pawn | EnemyType::Thrower | EnemyType::Diver | EnemyType::Armored => (),
+        EnemyType::ShieldNinja => {
+            enemy.insert(Shield);
+        }
+    }
+
+    Ok(enemy.id())
+}
+
+#[derive(Component, Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
 pub enum EnemyType {
+    #[default]
     Pawn,
+    ShieldNinja,
+    Thrower,
+    Diver,
+    Armored,
 }
 
 impl EnemyType {
-    pub fn get_texture(&self, game_assets: Res<GameAssets>) -> Handle<TextureAtlas> {
+    pub fn get_texture(&self, game_assets: &Res<GameAssets>) -> Handle<TextureAtlas> {
         match self {
             EnemyType::Pawn => game_assets.red_ninja.clone(),
+            EnemyType::ShieldNinja => game_assets.shield_ninja.clone(),
+            EnemyType::Thrower => game_assets.thrower_ninja.clone(),
+            EnemyType::Diver => game_assets.diver_ninja.clone(),
+            EnemyType::Armored => game_assets.armored_ninja.clone(),
         }
     }
 
     pub fn get_gravity(&self) -> f32 {
         match self {
             EnemyType::Pawn => 7.0,
+            EnemyType::ShieldNinja => 7.0,
+            EnemyType::Thrower => 7.0,
+            EnemyType::Diver => 3.0,
+            EnemyType::Armored => 8.0,
         }
     }
 
     pub fn get_speed(&self) -> f32 {
         match self {
             EnemyType::Pawn => 600.0,
+            EnemyType::ShieldNinja => 520.0,
+            EnemyType::Thrower => 560.0,
+            EnemyType::Diver => 480.0,
+            EnemyType::Armored => 540.0,
         }
     }
 
     pub fn get_trajectory(&self) -> Vec2 {
         match self {
             EnemyType::Pawn => Vec2::new(1.0, 1.0),
+            EnemyType::ShieldNinja => Vec2::new(1.0, 1.0),
+            EnemyType::Thrower => Vec2::new(1.0, 1.0),
+            EnemyType::Diver => Vec2::new(1.0, 0.6),
+            EnemyType::Armored => Vec2::new(1.0, 1.0),
         }
     }
 
     pub fn get_reflect_chance(&self) -> f32 {
         match self {
             EnemyType::Pawn => 0.20,
+            EnemyType::ShieldNinja => 0.0,
+            EnemyType::Thrower => 0.20,
+            EnemyType::Diver => 0.10,
+            EnemyType::Armored => 0.10,
         }
     }
 }
 
 pub fn enemy_movement(
     time: Res<Time>,
-    mut query: Query<(
-        Entity,
-        &mut Transform,
-        &mut Velocity,
-        &InitialEnemySpeed,
-        &mut WallHangingTimer,
-        &mut Enemy,
-        &Dimensions,
-    )>,
+    player_query: Query<&Transform, With<Player>>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &InitialEnemySpeed,
+            &mut WallHangingTimer,
+            &mut Enemy,
+            &Dimensions,
+            Option<&mut Diver>,
+        ),
+        (Without<Player>, Without<ScriptedPath>),
+    >,
     mut commands: Commands,
 ) {
     for (
@@ -145,6 +385,7 @@ pub fn enemy_movement(
         mut wall_hanging_timer,
         mut enemy,
         dimensions,
+        diver,
     ) in query.iter_mut()
     {
         let Bounds { right, left, .. } = calculate_bounds(&transform, Some(dimensions.0));
@@ -159,6 +400,18 @@ pub fn enemy_movement(
                 velocity.x *= -1.0;
                 velocity.y = initial_enemy_speed.0;
                 enemy.0 = EnemyState::Airborne;
+
+                let player_position =
+                    nearest_player(&player_query, transform.translation.truncate());
+                if let (Some(mut diver), Some(player_position)) = (diver, player_position) {
+                    let dive = player_position - transform.translation.truncate();
+
+                    // only dive when the player is away from the wall being left
+                    if dive.x.signum() == velocity.x.signum() {
+                        velocity.0 = dive.normalize() * velocity.length();
+                        diver.dive_timer.reset();
+                    }
+                }
             } else {
                 enemy.0 = EnemyState::WallHanging;
             }
@@ -167,15 +420,92 @@ pub fn enemy_movement(
             transform.translation.x += velocity.x * time.delta().as_secs_f32();
         }
 
-        if transform.translation.y > (WORLD_HEIGHT / 2.0) + 100.0 && enemy.0 != EnemyState::Dead {
+        let is_out_of_bounds = transform.translation.y > (WORLD_HEIGHT / 2.0) + 100.0
+            || transform.translation.y < -(WORLD_HEIGHT / 2.0) - 100.0;
+
+        if is_out_of_bounds && enemy.0 != EnemyState::Dead {
             commands.entity(entity).insert(MarkDespawn);
         }
     }
 }
 
-pub fn enemy_animator(mut query: Query<(&Enemy, &Velocity, &mut TextureAtlasSprite), With<Enemy>>) {
-    for (enemy, velocity, mut sprite) in query.iter_mut() {
-        if enemy.0 == EnemyState::WallHanging {
+/// Moves enemies along their scripted path and hands them back to `enemy_movement` at its end
+pub fn scripted_path_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut Velocity, &mut ScriptedPath)>,
+) {
+    for (entity, mut transform, mut velocity, mut path) in query.iter_mut() {
+        let target = match path.waypoints.get(path.next) {
+            Some(target) => *target,
+            None => {
+                commands.entity(entity).remove::<ScriptedPath>();
+                continue;
+            }
+        };
+
+        let to_target = target - transform.translation.truncate();
+        let step = path.speed * time.delta_seconds();
+
+        if to_target.length() <= step {
+            transform.translation = target.extend(transform.translation.z);
+            path.next += 1;
+        } else {
+            transform.translation += (to_target.normalize() * step).extend(0.0);
+        }
+
+        // keeps the animation facing the right way and carries the momentum past the last point
+        if to_target != Vec2::ZERO {
+            velocity.0 = to_target.normalize() * path.speed;
+        }
+    }
+}
+
+/// Curves diving enemies towards the player until their dive runs out
+pub fn diver_homing(
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Player>>,
+    mut query: Query<(&Transform, &mut Velocity, &Enemy, &mut Diver), Without<Player>>,
+) {
+    for (transform, mut velocity, enemy, mut diver) in query.iter_mut() {
+        if enemy.0 != EnemyState::Airborne || diver.dive_timer.tick(time.delta()).finished() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let desired = match nearest_player(&player_query, position) {
+            Some(player_position) => player_position - position,
+            None => continue,
+        };
+        if desired == Vec2::ZERO || velocity.0 == Vec2::ZERO {
+            continue;
+        }
+
+        let max_turn = diver.turn_rate * time.delta_seconds();
+        let turn = velocity.0.angle_between(desired).clamp(-max_turn, max_turn);
+        velocity.0 = Vec2::from_angle(turn).rotate(velocity.0);
+    }
+}
+
+pub fn enemy_animator(
+    mut query: Query<
+        (
+            &Enemy,
+            &Velocity,
+            &mut TextureAtlasSprite,
+            Option<&BlockingTimer>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    for (enemy, velocity, mut sprite, blocking_timer) in query.iter_mut() {
+        if blocking_timer.is_some() && enemy.0 != EnemyState::Dead {
+            if velocity.x > 0.0 {
+                sprite.index = 4;
+            } else {
+                sprite.index = 5;
+            }
+        } else if enemy.0 == EnemyState::WallHanging {
             if velocity.x > 0.0 {
                 sprite.index = 3;
             } else {
@@ -191,21 +521,132 @@ pub fn enemy_animator(mut query: Query<(&Enemy, &Velocity, &mut TextureAtlasSpri
     }
 }
 
-pub fn gravity_system(mut query: Query<(&mut Velocity, &mut Gravity), With<Enemy>>) {
+pub fn blocking_timer_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut BlockingTimer)>,
+) {
+    for (entity, mut blocking_timer) in query.iter_mut() {
+        if blocking_timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<BlockingTimer>();
+        }
+    }
+}
+
+pub fn gravity_system(
+    mut query: Query<(&mut Velocity, &mut Gravity), (With<Enemy>, Without<ScriptedPath>)>,
+) {
     for (mut velocity, gravity) in query.iter_mut() {
         velocity.y -= gravity.0;
     }
 }
 
+pub fn enemy_hit_system(
+    mut commands: Commands,
+    mut enemy_hit_events: EventReader<EnemyHitEvent>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut TextureAtlasSprite,
+            Option<&mut HitFlashTimer>,
+        ),
+        Or<(With<Enemy>, With<GroundEnemy>)>,
+    >,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for event in enemy_hit_events.iter() {
+        if let Ok((mut transform, mut sprite, hit_flash)) = query.get_mut(event.entity) {
+            transform.translation +=
+                (event.knockback.normalize_or_zero() * ENEMY_KNOCKBACK_DISTANCE).extend(0.0);
+
+            start_hit_flash(&mut commands, event.entity, &mut sprite, hit_flash);
+        }
+
+        sfx_events.send(SFXEvents::ClankSound);
+    }
+}
+
+/// Tints a damaged sprite white for `HIT_FLASH_SEC`, see `hit_flash_system`
+///
+/// Hitting a sprite that is already flashing only restarts its flash, so the colour it gets
+/// back afterwards is always the one it had before the first hit.
+pub fn start_hit_flash(
+    commands: &mut Commands,
+    entity: Entity,
+    sprite: &mut TextureAtlasSprite,
+    hit_flash: Option<Mut<HitFlashTimer>>,
+) {
+    match hit_flash {
+        Some(mut hit_flash) => hit_flash.timer.reset(),
+        // a flash started earlier this frame is not inserted yet but already tinted the sprite
+        None if sprite.color != HIT_FLASH_COLOR => {
+            commands.entity(entity).insert(HitFlashTimer {
+                timer: Timer::from_seconds(HIT_FLASH_SEC, TimerMode::Once),
+                original_color: sprite.color,
+            });
+        }
+        None => (),
+    }
+
+    sprite.color = HIT_FLASH_COLOR;
+}
+
+/// Puts the original colour back on flashing sprites once their flash is over
+pub fn hit_flash_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut HitFlashTimer, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut hit_flash, mut sprite) in query.iter_mut() {
+        if hit_flash.timer.tick(time.delta()).finished() {
+            sprite.color = hit_flash.original_color;
+            commands.entity(entity).remove::<HitFlashTimer>();
+        }
+    }
+}
+
+pub fn enemy_death_system(
+    mut commands: Commands,
+    mut enemy_killed_events: EventReader<EnemyKilledEvent>,
+    mut sfx_events: EventWriter<SFXEvents>,
+    mut score: ResMut<Score>,
+    game_assets: Res<GameAssets>,
+) {
+    for event in enemy_killed_events.iter() {
+        commands.entity(event.entity).insert(MarkDespawn);
+        commands.spawn(DeathEffectBundle::new(&game_assets, event.position));
+        sfx_events.send(SFXEvents::DeathSound);
+
+        score.0 += ENEMY_KILL_SCORE;
+    }
+}
+
 pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_run_criteria(pause_game)
-                .with_system(enemy_movement)
-                .with_system(enemy_animator)
-                .with_system(gravity_system),
-        );
+        let enemy_config = EnemyConfig(from_bytes(include_bytes!("../data/enemies.ron")).unwrap());
+
+        app.insert_resource(enemy_config)
+            .add_event::<EnemyKilledEvent>()
+            .add_event::<EnemyHitEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(enemy_movement)
+                    .with_system(scripted_path_movement)
+                    .with_system(diver_homing)
+                    .with_system(enemy_animator)
+                    .with_system(blocking_timer_system)
+                    .with_system(hit_flash_system)
+                    .with_system(gravity_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(enemy_hit_system)
+                    .with_system(enemy_death_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage).with_system(despawner::<Enemy>),
+            );
     }
 }
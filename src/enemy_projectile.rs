@@ -0,0 +1,182 @@
+use crate::{pause_game, prelude::*};
+use rand::{thread_rng, Rng};
+use serde::Deserialize;
+
+pub const PROJECTILE_SPAWN_OFFSET: f32 = 24.0;
+
+/// Largest aiming error in degrees, reached by a thrower with an accuracy of 0
+pub const MAX_AIM_ERROR_DEG: f32 = 35.0;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProjectileSettings {
+    pub fire_interval_sec: f32,
+    /// Between 0 and 1, where 1 always aims straight at the player
+    pub accuracy: f32,
+    pub speed: f32,
+}
+
+#[derive(Component)]
+pub struct EnemyProjectile;
+
+/// Lets an enemy throw projectiles at the player while hanging on a wall
+#[derive(Component, Debug)]
+pub struct ProjectileThrower {
+    pub fire_timer: Timer,
+    pub accuracy: f32,
+    pub speed: f32,
+}
+
+impl ProjectileThrower {
+    pub fn new(settings: ProjectileSettings) -> Self {
+        ProjectileThrower {
+            fire_timer: Timer::from_seconds(settings.fire_interval_sec, TimerMode::Repeating),
+            accuracy: settings.accuracy.clamp(0.0, 1.0),
+            speed: settings.speed,
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct EnemyProjectileBundle {
+    enemy_projectile: EnemyProjectile,
+    velocity: Velocity,
+    hitbox: HitBox,
+    sprite_bundle: SpriteBundle,
+    previous_position: PreviousPosition,
+    collision_layers: CollisionLayers,
+}
+
+impl EnemyProjectileBundle {
+    pub fn new(
+        game_assets: &Res<GameAssets>,
+        starting_pos: Vec3,
+        direction: Vec2,
+        speed: f32,
+    ) -> Self {
+        let direction = direction.try_normalize().unwrap_or(Vec2::Y);
+
+        EnemyProjectileBundle {
+            enemy_projectile: EnemyProjectile,
+            velocity: Velocity(direction * speed),
+            hitbox: HitBox::oriented(Vec2::new(14.0, 4.0), direction),
+            previous_position: PreviousPosition(starting_pos.truncate()),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::EnemyProjectile,
+                &[CollisionLayer::Player],
+            ),
+            sprite_bundle: SpriteBundle {
+                texture: game_assets.enemy_projectile.clone(),
+                transform: Transform {
+                    translation: starting_pos,
+                    rotation: Quat::from_rotation_z(direction.y.atan2(direction.x)),
+                    ..default()
+                },
+                ..default()
+            },
+        }
+    }
+}
+
+pub struct EnemyProjectilePlugin;
+impl Plugin for EnemyProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_run_criteria(pause_game)
+                .with_system(enemy_throwing_system)
+                .with_system(enemy_projectile_movement),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_system(enemy_projectile_hit_player.after(detect_collisions)),
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::EndStage).with_system(despawner::<EnemyProjectile>),
+        );
+    }
+}
+
+pub fn enemy_throwing_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_assets: Res<GameAssets>,
+    player_query: Query<&Transform, With<Player>>,
+    mut thrower_query: Query<(&Transform, &Enemy, &mut ProjectileThrower), Without<Player>>,
+) {
+    let mut rng = thread_rng();
+
+    for (transform, enemy, mut thrower) in thrower_query.iter_mut() {
+        if enemy.0 != EnemyState::WallHanging {
+            thrower.fire_timer.reset();
+            continue;
+        }
+
+        if !thrower.fire_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let max_error = (1.0 - thrower.accuracy) * MAX_AIM_ERROR_DEG.to_radians();
+        let position = transform.translation.truncate();
+        let aim = match nearest_player(&player_query, position) {
+            Some(player_position) => player_position - position,
+            None => continue,
+        };
+        let direction = Vec2::from_angle(rng.gen_range(-max_error..=max_error)).rotate(aim);
+
+        commands.spawn(EnemyProjectileBundle::new(
+            &game_assets,
+            transform.translation
+                + (direction.normalize_or_zero() * PROJECTILE_SPAWN_OFFSET).extend(0.0),
+            direction,
+            thrower.speed,
+        ));
+    }
+}
+
+pub fn enemy_projectile_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<
+        (Entity, &mut Transform, &Velocity, &mut PreviousPosition),
+        (With<EnemyProjectile>, Without<MarkDespawn>),
+    >,
+) {
+    for (entity, mut transform, velocity, mut previous_position) in query.iter_mut() {
+        previous_position.0 = transform.translation.truncate();
+        transform.translation += velocity.0.extend(0.0) * time.delta_seconds();
+
+        let is_out_of_bounds = transform.translation.x < LEFT_WALL - 50.0
+            || transform.translation.x > RIGHT_WALL + 50.0
+            || transform.translation.y.abs() > (WORLD_HEIGHT / 2.0) + 100.0;
+
+        if is_out_of_bounds {
+            commands.entity(entity).insert(MarkDespawn);
+        }
+    }
+}
+
+/// Each enemy projectile hurts the first vulnerable player it reaches
+pub fn enemy_projectile_hit_player(
+    mut commands: Commands,
+    mut projectile_hit_player_events: EventReader<ProjectileHitPlayerEvent>,
+    projectile_query: Query<(), (With<EnemyProjectile>, Without<MarkDespawn>)>,
+    player_query: Query<(), (With<Player>, Without<Invulnerable>)>,
+    mut player_hit_events: EventWriter<PlayerHitEvent>,
+) {
+    let mut spent_projectiles = Vec::new();
+
+    for event in projectile_hit_player_events.iter() {
+        if spent_projectiles.contains(&event.projectile)
+            || !projectile_query.contains(event.projectile)
+            || !player_query.contains(event.player)
+        {
+            continue;
+        }
+
+        spent_projectiles.push(event.projectile);
+        commands.entity(event.projectile).insert(MarkDespawn);
+        player_hit_events.send(PlayerHitEvent {
+            entity: event.player,
+        });
+    }
+}
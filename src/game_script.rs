@@ -1,49 +1,221 @@
 use crate::{asset_loading::AssetHandler, assets::GameAssets, prelude::*};
+use ron::de::from_bytes;
+use serde::Deserialize;
+
+/// Seconds the end of stage screen stays up before moving on
+pub const END_STAGE_SEC: f32 = 8.0;
 
 pub struct GameScriptPlugin;
 impl Plugin for GameScriptPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<GameScriptState>()
-            .add_system_set(SystemSet::on_update(GameState::LoadWorld).with_system(load_state));
+        let stages: Vec<StageEntry> =
+            from_bytes(include_bytes!("../data/game_script.ron")).unwrap();
+
+        if stages.is_empty() {
+            panic!("data/game_script.ron needs at least one stage");
+        }
+
+        app.insert_resource(GameScriptState::new(stages))
+            .init_resource::<EndStageTimer>()
+            .add_system_set(SystemSet::on_update(GameState::LoadWorld).with_system(load_state))
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(play_stage_music))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Platforming).with_system(play_stage_music),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::EndStage).with_system(start_end_stage_timer),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::EndStage).with_system(advance_game_script),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameComplete).with_system(game_complete_setup),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameComplete).with_system(game_complete_input),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameComplete)
+                    .with_system(despawner::<OnGameCompleteScreen>),
+            );
     }
 }
 
-#[derive(Debug)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum GameScript {
     BonusStage,
+    /// Side-scrolling stage using the layout named `level` in `data/platforming.ron`
+    Platforming {
+        level: String,
+    },
+    /// Side-scrolling stage built from the Tiled map at `map`, relative to `assets/`
+    TiledStage {
+        map: String,
+    },
+}
+
+/// One entry of the stage sequence in `data/game_script.ron`
+#[derive(Deserialize, Debug, Clone)]
+pub struct StageEntry {
+    pub name: String,
+    pub script: GameScript,
 }
 
 #[derive(Resource)]
 pub struct GameScriptState {
-    pub current: GameScript,
+    pub stages: Vec<StageEntry>,
+    pub index: usize,
+    /// The run was quit from the pause menu and goes back to the menu once the stage ends
+    pub quit: bool,
 }
 
-impl Default for GameScriptState {
-    fn default() -> Self {
+impl GameScriptState {
+    pub fn new(stages: Vec<StageEntry>) -> Self {
         GameScriptState {
-            current: GameScript::BonusStage,
+            stages,
+            index: 0,
+            quit: false,
+        }
+    }
+
+    pub fn current(&self) -> &StageEntry {
+        &self.stages[self.index]
+    }
+
+    /// Moves to the next stage, returning `false` once the last stage is done
+    pub fn next(&mut self) -> bool {
+        if self.index + 1 >= self.stages.len() {
+            return false;
         }
+
+        println!("Moving from {:?}", self.current());
+        self.index += 1;
+        println!("to {:?}", self.current());
+
+        true
+    }
+
+    pub fn restart(&mut self) {
+        self.index = 0;
+        self.quit = false;
+    }
+}
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct EndStageTimer(pub Timer);
+
+impl Default for EndStageTimer {
+    fn default() -> Self {
+        EndStageTimer(Timer::from_seconds(END_STAGE_SEC, TimerMode::Once))
     }
 }
 
-// impl GameScriptState {
-//     pub fn next(&mut self) {
-//         println!("Moving from {:?}", self.current);
-//         // self.current = match self.current {};
-//         println!("to {:?}", self.current);
-//     }
-// }
+#[derive(Component)]
+struct OnGameCompleteScreen;
 
 fn load_state(
     mut assets_handler: AssetHandler,
-    mut game_assets: ResMut<GameAssets>, 
+    mut game_assets: ResMut<GameAssets>,
     game_script_state: Res<GameScriptState>,
+    game_mode: Res<GameMode>,
 ) {
-    println!("Loading state {:?}", game_script_state.current);
-    match game_script_state.current {
-        GameScript::BonusStage => {
-            assets_handler.load(GameState::InGame, &mut game_assets)
+    // the other modes are all played on the bonus stage
+    if *game_mode != GameMode::Story {
+        assets_handler.load(GameState::InGame, &mut game_assets);
+        return;
+    }
+
+    println!("Loading state {:?}", game_script_state.current());
+    match &game_script_state.current().script {
+        GameScript::BonusStage => assets_handler.load(GameState::InGame, &mut game_assets),
+        GameScript::Platforming { .. } => {
+            assets_handler.load(GameState::Platforming, &mut game_assets)
         }
-        _ => (),
+        GameScript::TiledStage { map } => {
+            let mut tiled_map = Handle::default();
+            assets_handler.add_tiled_map(&mut tiled_map, map);
+            game_assets.tiled_maps.insert(map.clone(), tiled_map);
+            assets_handler.load(GameState::Platforming, &mut game_assets);
+        }
+    }
+}
+
+fn play_stage_music(mut bgm_events: EventWriter<BGMEvents>) {
+    bgm_events.send(BGMEvents::InGameMusic);
+}
+
+fn start_end_stage_timer(mut end_stage_timer: ResMut<EndStageTimer>) {
+    end_stage_timer.reset();
+}
+
+/// Loads the next stage of a story run, or leaves the run once there is nothing left to play
+fn advance_game_script(
+    time: Res<Time>,
+    game_mode: Res<GameMode>,
+    lives: Res<Lives>,
+    mut end_stage_timer: ResMut<EndStageTimer>,
+    mut game_script_state: ResMut<GameScriptState>,
+    mut asset_handler: AssetHandler,
+    mut game_assets: ResMut<GameAssets>,
+) {
+    if !end_stage_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // a story run that ran out of lives or was quit is over as well
+    if *game_mode != GameMode::Story || lives.0 == 0 || game_script_state.quit {
+        game_script_state.quit = false;
+        asset_handler.load(GameState::MainMenu, &mut game_assets);
+    } else if game_script_state.next() {
+        asset_handler.load(GameState::LoadWorld, &mut game_assets);
+    } else {
+        asset_handler.load(GameState::GameComplete, &mut game_assets);
+    }
+}
+
+fn game_complete_setup(mut commands: Commands, game_assets: Res<GameAssets>, score: Res<Score>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            OnGameCompleteScreen,
+        ))
+        .with_children(|parent| {
+            for text in ["GAME COMPLETE".to_string(), format!("SCORE {}", score.0)] {
+                parent.spawn(
+                    TextBundle::from_section(
+                        text,
+                        TextStyle {
+                            font: game_assets.menu_font.clone(),
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(25.0)),
+                        ..default()
+                    }),
+                );
+            }
+        });
+}
+
+fn game_complete_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut asset_handler: AssetHandler,
+    mut game_assets: ResMut<GameAssets>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    if keyboard_input.any_just_pressed(vec![KeyCode::Return, KeyCode::V]) {
+        sfx_events.send(SFXEvents::MenuSFX);
+        asset_handler.load(GameState::MainMenu, &mut game_assets);
     }
 }
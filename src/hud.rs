@@ -0,0 +1,373 @@
+use crate::{pause_game, prelude::*};
+
+pub const WAVE_BANNER_SEC: f32 = 2.0;
+
+pub struct HudPlugin;
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(hud_setup))
+            .add_system_set(SystemSet::on_enter(GameState::Platforming).with_system(hud_setup))
+            .add_system_set(
+                SystemSet::on_update(GameState::Platforming)
+                    .with_system(update_score_text)
+                    .with_system(update_bomb_text),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Platforming).with_system(despawner::<OnHud>),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(update_score_text)
+                    .with_system(update_ninjutsu_text)
+                    .with_system(update_lives_text)
+                    .with_system(update_time_attack_text)
+                    .with_system(update_practice_text)
+                    .with_system(update_coop_text)
+                    .with_system(update_power_up_text)
+                    .with_system(wave_banners),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(wave_banner_timer),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(despawner::<OnHud>));
+    }
+}
+
+#[derive(Component)]
+pub struct OnHud;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Component)]
+pub struct NinjutsuText;
+
+#[derive(Component)]
+pub struct PowerUpText;
+
+#[derive(Component)]
+pub struct LivesText;
+
+#[derive(Component)]
+pub struct TimeAttackText;
+
+#[derive(Component)]
+pub struct PracticeText;
+
+#[derive(Component)]
+pub struct BombText;
+
+#[derive(Component)]
+pub struct CoopText;
+
+/// Centered text announcing wave starts and clears
+#[derive(Component, Deref, DerefMut)]
+pub struct WaveBanner(pub Timer);
+
+fn hud_text(text: String, game_assets: &Res<GameAssets>) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font: game_assets.menu_font.clone(),
+            font_size: 16.0,
+            color: Color::WHITE,
+        },
+    )
+}
+
+/// Full-screen column centering the lines of a results screen
+pub fn results_screen() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+/// One line of a results screen
+pub fn results_text(text: String, game_assets: &Res<GameAssets>) -> TextBundle {
+    hud_text(text, game_assets).with_style(Style {
+        margin: UiRect::bottom(Val::Px(6.0)),
+        ..default()
+    })
+}
+
+fn hud_setup(mut commands: Commands, game_assets: Res<GameAssets>, score: Res<Score>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexStart,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            OnHud,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                hud_text(format!("SCORE {}", score.0), &game_assets),
+                ScoreText,
+            ));
+            parent.spawn((hud_text(String::new(), &game_assets), PowerUpText));
+            parent.spawn((hud_text(String::new(), &game_assets), LivesText));
+            parent.spawn((hud_text(String::new(), &game_assets), TimeAttackText));
+            parent.spawn((hud_text(String::new(), &game_assets), PracticeText));
+            parent.spawn((hud_text(String::new(), &game_assets), BombText));
+            parent.spawn((hud_text(String::new(), &game_assets), CoopText));
+            parent.spawn((hud_text(String::new(), &game_assets), NinjutsuText));
+        });
+}
+
+fn update_score_text(score: Res<Score>, mut query: Query<&mut Text, With<ScoreText>>) {
+    if !score.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("SCORE {}", score.0);
+    }
+}
+
+fn update_ninjutsu_text(
+    charges_query: Query<(&NinjutsuCharges, &PlayerSlot)>,
+    changed_query: Query<(), Changed<NinjutsuCharges>>,
+    mut text_query: Query<&mut Text, With<NinjutsuText>>,
+) {
+    if changed_query.is_empty() {
+        return;
+    }
+
+    let mut charges = charges_query.iter().collect::<Vec<_>>();
+    charges.sort_by_key(|(_, slot)| **slot);
+
+    let ninjutsu_text = charges
+        .iter()
+        .map(|(charges, _)| format!("x{}", charges.0))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("NINJUTSU {}", ninjutsu_text);
+    }
+}
+
+fn update_lives_text(
+    game_mode: Res<GameMode>,
+    lives: Res<Lives>,
+    mut query: Query<(&mut Text, ChangeTrackers<LivesText>)>,
+) {
+    if *game_mode != GameMode::Endless && *game_mode != GameMode::Story {
+        return;
+    }
+
+    for (mut text, lives_text) in query.iter_mut() {
+        // story lives carry over from earlier stages, so a new HUD needs them too
+        if lives.is_changed() || lives_text.is_added() {
+            text.sections[0].value = format!("LIVES {}", lives.0);
+        }
+    }
+}
+
+/// Live clock followed by the latest split and how it compares to the personal best
+fn update_time_attack_text(
+    game_mode: Res<GameMode>,
+    time_attack_run: Res<TimeAttackRun>,
+    mut query: Query<&mut Text, With<TimeAttackText>>,
+) {
+    if *game_mode != GameMode::TimeAttack {
+        return;
+    }
+
+    let mut time_attack_text = format!("TIME {:.2}", time_attack_run.clock.elapsed_secs());
+
+    if let Some(split) = time_attack_run.splits.last() {
+        let index = time_attack_run.splits.len() - 1;
+        time_attack_text += &format!("\nWAVE {} {:.2}", index + 1, split);
+
+        if let Some(delta) = time_attack_run.delta(index) {
+            time_attack_text += &format!(" {:+.2}", delta);
+        }
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = time_attack_text.clone();
+    }
+}
+
+fn update_practice_text(
+    game_mode: Res<GameMode>,
+    practice_hits: Res<PracticeHits>,
+    mut query: Query<&mut Text, With<PracticeText>>,
+) {
+    if *game_mode != GameMode::Practice || !practice_hits.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("PRACTICE HITS {}", practice_hits.0);
+    }
+}
+
+fn update_bomb_text(
+    stage_objective: Res<StageObjective>,
+    mut query: Query<&mut Text, With<BombText>>,
+) {
+    if stage_objective.total == 0 {
+        return;
+    }
+
+    let bomb_text = if stage_objective.is_complete() {
+        "EXIT OPEN".to_string()
+    } else {
+        format!(
+            "BOMBS {}/{}",
+            stage_objective.defused, stage_objective.total
+        )
+    };
+
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != bomb_text {
+            text.sections[0].value = bomb_text.clone();
+        }
+    }
+}
+
+/// Kill score and lives left of each player, players who dropped out show no lives
+fn update_coop_text(
+    game_mode: Res<GameMode>,
+    coop_scores: Res<CoopScores>,
+    lives_query: Query<(&PlayerSlot, &PlayerLives)>,
+    mut query: Query<&mut Text, With<CoopText>>,
+) {
+    if *game_mode != GameMode::Coop {
+        return;
+    }
+
+    let coop_text = [PlayerSlot::One, PlayerSlot::Two]
+        .iter()
+        .map(|slot| {
+            let lives = lives_query
+                .iter()
+                .find(|(player_slot, _)| *player_slot == slot)
+                .map_or(0, |(_, lives)| lives.0);
+            let score = coop_scores.0.get(slot).copied().unwrap_or(0);
+
+            format!("{} {} LIVES {}", slot.label(), score, lives)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != coop_text {
+            text.sections[0].value = coop_text.clone();
+        }
+    }
+}
+
+fn update_power_up_text(
+    power_ups_query: Query<(&ActivePowerUps, &PlayerSlot)>,
+    mut text_query: Query<&mut Text, With<PowerUpText>>,
+) {
+    let mut players = power_ups_query
+        .iter()
+        .filter(|(active_power_ups, _)| !active_power_ups.0.is_empty())
+        .collect::<Vec<_>>();
+    players.sort_by_key(|(_, slot)| **slot);
+
+    let power_up_text = players
+        .iter()
+        .map(|(active_power_ups, slot)| {
+            let mut power_ups = active_power_ups
+                .0
+                .iter()
+                .map(|(kind, timer)| {
+                    let seconds_left = timer.duration().saturating_sub(timer.elapsed());
+                    format!("{} {}", kind.label(), seconds_left.as_secs_f32().ceil())
+                })
+                .collect::<Vec<String>>();
+            power_ups.sort();
+
+            format!("{} {}", slot.label(), power_ups.join("  "))
+        })
+        .collect::<Vec<String>>()
+        .join("   ");
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = power_up_text.clone();
+    }
+}
+
+fn wave_banners(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut wave_started_events: EventReader<WaveStartedEvent>,
+    mut wave_cleared_events: EventReader<WaveClearedEvent>,
+    banner_query: Query<Entity, With<WaveBanner>>,
+) {
+    let started = wave_started_events
+        .iter()
+        .map(|event| format!("WAVE {}", event.number));
+    let cleared = wave_cleared_events
+        .iter()
+        .filter(|event| event.full_clear)
+        .map(|event| {
+            if event.perfect {
+                format!("PERFECT +{}", event.bonus)
+            } else {
+                format!("WAVE CLEAR +{}", event.bonus)
+            }
+        });
+
+    // a new banner replaces whatever is still on screen
+    let banner = match cleared.chain(started).last() {
+        Some(banner) => banner,
+        None => return,
+    };
+
+    for entity in banner_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            WaveBanner(Timer::from_seconds(WAVE_BANNER_SEC, TimerMode::Once)),
+            OnHud,
+        ))
+        .with_children(|parent| {
+            parent.spawn(hud_text(banner, &game_assets));
+        });
+}
+
+fn wave_banner_timer(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut WaveBanner)>,
+) {
+    for (entity, mut banner_timer) in query.iter_mut() {
+        if banner_timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
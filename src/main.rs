@@ -4,19 +4,37 @@ mod prelude {
     pub use crate::assets::*;
     pub use crate::audio::*;
     pub use crate::background::*;
+    pub use crate::bombs::*;
+    pub use crate::boss::*;
     pub use crate::camera::*;
     pub use crate::collision::*;
     pub use crate::components::*;
     pub use crate::constants::*;
+    pub use crate::coop::*;
     pub use crate::death_effect::*;
+    pub use crate::difficulty::*;
+    pub use crate::dog::*;
+    pub use crate::endless::*;
     pub use crate::enemy::*;
+    pub use crate::enemy_projectile::*;
     pub use crate::game_script::*;
+    pub use crate::hud::*;
+    pub use crate::melee::*;
+    pub use crate::ninjutsu::*;
     pub use crate::pause_menu::*;
+    pub use crate::platforming::*;
     pub use crate::player::*;
+    pub use crate::player_state::*;
+    pub use crate::power_up::*;
+    pub use crate::practice::*;
     pub use crate::resources::*;
     pub use crate::roof::*;
+    pub use crate::save::*;
     pub use crate::shuriken::*;
+    pub use crate::spatial::*;
     pub use crate::splash::*;
+    pub use crate::tiled::*;
+    pub use crate::time_attack::*;
     pub use crate::title_screen::*;
     pub use crate::transition::*;
     pub use crate::utils::*;
@@ -49,38 +67,55 @@ mod asset_loading;
 mod assets;
 mod audio;
 mod background;
+mod bombs;
+mod boss;
 mod camera;
 mod collision;
 mod components;
 mod constants;
+mod coop;
 mod death_effect;
+mod difficulty;
+mod dog;
+mod endless;
 mod enemy;
+mod enemy_projectile;
 mod game_script;
+mod hud;
 mod main_menu;
+mod melee;
+mod ninjutsu;
 mod pause_menu;
+mod platforming;
 mod player;
+mod player_state;
+mod power_up;
+mod practice;
 mod resources;
 mod roof;
+mod save;
 mod shuriken;
+mod spatial;
 mod splash;
+mod tiled;
+mod time_attack;
 mod title_screen;
 mod transition;
 mod utils;
 mod walls;
 mod waves;
+#[cfg(test)]
+mod tests;
 // mod input;
 
 pub fn pause_game(event: Res<PauseEvent>, query: Query<&Player>) -> ShouldRun {
-    let player = query.get_single();
-    match player {
-        Ok(p) => {
-            if *event == PauseEvent::Unpaused && p.1 == LevelState::Start {
-                ShouldRun::Yes
-            } else {
-                ShouldRun::No
-            }
-        }
-        Err(_) => ShouldRun::No,
+    // the stage gets going as soon as one player has flipped in
+    let started = query.iter().any(|player| player.1 == LevelState::Start);
+
+    if *event == PauseEvent::Unpaused && started {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
     }
 }
 
@@ -104,8 +139,12 @@ fn main() {
                 .set(ImagePlugin::default_nearest()),
         )
         .init_resource::<PauseEvent>()
+        .init_resource::<Score>()
+        .init_resource::<GameMode>()
+        .init_resource::<Lives>()
         .add_plugin(CameraPlugin)
         .add_plugin(PlayerPlugin)
+        .add_plugin(PlayerStatePlugin)
         .add_plugin(GameAudioPlugin)
         .add_plugin(AssetsLoadingPlugin)
         .add_plugin(AssetsPlugin)
@@ -114,8 +153,23 @@ fn main() {
         .add_plugin(GameScriptPlugin)
         .add_plugin(RoofPlugin)
         .add_plugin(EnemyPlugin)
+        .add_plugin(EnemyProjectilePlugin)
+        .add_plugin(BossPlugin)
         .add_plugin(WallPlugin)
         .add_plugin(ShurikenPlugin)
+        .add_plugin(MeleePlugin)
+        .add_plugin(NinjutsuPlugin)
+        .add_plugin(PowerUpPlugin)
+        .add_plugin(DifficultyPlugin)
+        .add_plugin(EndlessPlugin)
+        .add_plugin(TimeAttackPlugin)
+        .add_plugin(PracticePlugin)
+        .add_plugin(CoopPlugin)
+        .add_plugin(PlatformingPlugin)
+        .add_plugin(BombsPlugin)
+        .add_plugin(DogPlugin)
+        .add_plugin(TiledPlugin)
+        .add_plugin(HudPlugin)
         .add_plugin(PauseMenuPlugin)
         .add_plugin(EasingsPlugin)
         .add_plugin(WavePlugin)
@@ -2,6 +2,47 @@ use bevy::app::AppExit;
 
 use crate::prelude::*;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuOption {
+    Start,
+    Endless,
+    TimeAttack,
+    Practice,
+    Coop,
+    Difficulty,
+    Quit,
+}
+
+impl MenuOption {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MenuOption::Start => "Start",
+            MenuOption::Endless => "Endless",
+            MenuOption::TimeAttack => "Time Attack",
+            MenuOption::Practice => "Practice",
+            MenuOption::Coop => "Co-op",
+            MenuOption::Difficulty => "Difficulty",
+            MenuOption::Quit => "Quit",
+        }
+    }
+}
+
+/// Text of the difficulty entry, which shows the difficulty it is set to
+fn difficulty_label(difficulty: Difficulty) -> String {
+    format!("{} {:?}", MenuOption::Difficulty.label(), difficulty)
+}
+
+/// Main menu entries from top to bottom
+pub const MENU_OPTIONS: [MenuOption; 7] = [
+    MenuOption::Start,
+    MenuOption::Endless,
+    MenuOption::TimeAttack,
+    MenuOption::Practice,
+    MenuOption::Coop,
+    MenuOption::Difficulty,
+    MenuOption::Quit,
+];
+
 pub struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
@@ -12,7 +53,8 @@ impl Plugin for MainMenuPlugin {
                 SystemSet::on_update(GameState::MainMenu)
                     .with_system(handle_current_selection)
                     .with_system(keyboard_input_system)
-                    .with_system(navigate_menu),
+                    .with_system(navigate_menu)
+                    .with_system(update_difficulty_label),
             )
             .add_system_set(
                 SystemSet::on_exit(GameState::MainMenu).with_system(despawner::<OnMainMenuScreen>),
@@ -35,6 +77,9 @@ pub struct MenuButton;
 #[derive(Component, Debug)]
 pub struct MenuSelector;
 
+#[derive(Component, Debug)]
+pub struct DifficultyButton;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NavigationEvent {
     Up,
@@ -98,7 +143,11 @@ pub fn load(asset_handler: &mut AssetHandler, game_assets: &mut ResMut<GameAsset
 #[derive(Component)]
 struct OnMainMenuScreen;
 
-fn main_menu_setup(mut commands: Commands, game_assets: Res<GameAssets>) {
+fn main_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    difficulty: Res<DifficultyConfig>,
+) {
     commands
         .spawn((
             NodeBundle {
@@ -114,45 +163,46 @@ fn main_menu_setup(mut commands: Commands, game_assets: Res<GameAssets>) {
             OnMainMenuScreen,
         ))
         .with_children(|parent| {
-            parent
-                .spawn((
+            for (index, option) in MENU_OPTIONS.iter().enumerate() {
+                let label = match option {
+                    MenuOption::Difficulty => difficulty_label(difficulty.current),
+                    _ => option.label().to_string(),
+                };
+                let mut button = parent.spawn((
                     MenuBundle::new(
-                        "Start",
-                        0,
+                        &label,
+                        index,
                         game_assets.menu_font.clone(),
                         TextColor::default(),
                     ),
                     MenuButton,
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        ImageBundle {
-                            image: UiImage(game_assets.menu_arrow.clone()),
-                            style: Style {
-                                
-                                position: UiRect {
-                                    right: Val::Px(35.0),
-                                    top: Val::Px(5.0),
+                ));
+
+                if *option == MenuOption::Difficulty {
+                    button.insert(DifficultyButton);
+                }
+
+                if index == 0 {
+                    button.with_children(|parent| {
+                        parent.spawn((
+                            ImageBundle {
+                                image: UiImage(game_assets.menu_arrow.clone()),
+                                style: Style {
+                                    position: UiRect {
+                                        right: Val::Px(35.0),
+                                        top: Val::Px(5.0),
+                                        ..Default::default()
+                                    },
+                                    size: Size::new(Val::Px(20.0), Val::Px(20.0)),
                                     ..Default::default()
                                 },
-                                size: Size::new(Val::Px(20.0), Val::Px(20.0)),
                                 ..Default::default()
                             },
-                            ..Default::default()
-                        },
-                        MenuSelector,
-                    ));
-                });
-
-            parent.spawn((
-                MenuBundle::new(
-                    "Quit",
-                    1,
-                    game_assets.menu_font.clone(),
-                    TextColor::default(),
-                ),
-                MenuButton,
-            ));
+                            MenuSelector,
+                        ));
+                    });
+                }
+            }
         });
 }
 
@@ -180,7 +230,10 @@ pub fn handle_current_selection(
     selector_query: Query<Entity, With<MenuSelector>>,
     current: ResMut<CurrentSelection>,
 ) {
-    let selector = selector_query.single();
+    let selector = match selector_query.get_single() {
+        Ok(selector) => selector,
+        Err(_) => return,
+    };
     for (entity, selectables, mut text) in query.iter_mut() {
         for section in text.sections.iter_mut() {
             if current.index == selectables.key {
@@ -198,33 +251,70 @@ pub fn navigate_menu(
     mut current: ResMut<CurrentSelection>,
     mut asset_handler: AssetHandler,
     mut game_assets: ResMut<GameAssets>,
+    mut game_mode: ResMut<GameMode>,
+    mut game_script_state: ResMut<GameScriptState>,
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+    mut difficulty: ResMut<DifficultyConfig>,
     mut app_exit_events: EventWriter<AppExit>,
 ) {
     for navigation_event in reader.iter() {
         if navigation_event == &NavigationEvent::Up {
-            if current.index == 0 {
-                current.index += 1;
-            } else if current.index == 1 {
-                current.index -= 1;
-            }
+            current.index = (current.index + MENU_OPTIONS.len() - 1) % MENU_OPTIONS.len();
         }
 
         if navigation_event == &NavigationEvent::Down {
-            if current.index == 0 {
-                current.index += 1;
-            } else if current.index == 1 {
-                current.index -= 1;
-            }
+            current.index = (current.index + 1) % MENU_OPTIONS.len();
         }
 
         if navigation_event == &NavigationEvent::Select {
-            if current.index == 0 {
-                asset_handler.load(GameState::LoadWorld, &mut game_assets);
-            } else if current.index == 1 {
-                app_exit_events.send(AppExit);
-                current.index -= 1;
-
+            match MENU_OPTIONS[current.index] {
+                MenuOption::Start => {
+                    *game_mode = GameMode::Story;
+                    game_script_state.restart();
+                    score.0 = 0;
+                    lives.0 = STORY_LIVES;
+                    asset_handler.load(GameState::LoadWorld, &mut game_assets);
+                }
+                MenuOption::Endless => {
+                    *game_mode = GameMode::Endless;
+                    asset_handler.load(GameState::LoadWorld, &mut game_assets);
+                }
+                MenuOption::TimeAttack => {
+                    *game_mode = GameMode::TimeAttack;
+                    asset_handler.load(GameState::LoadWorld, &mut game_assets);
+                }
+                MenuOption::Practice => {
+                    *game_mode = GameMode::Practice;
+                    asset_handler.load(GameState::PracticeMenu, &mut game_assets);
+                }
+                MenuOption::Coop => {
+                    *game_mode = GameMode::Coop;
+                    asset_handler.load(GameState::LoadWorld, &mut game_assets);
+                }
+                MenuOption::Difficulty => {
+                    difficulty.current = difficulty.current.next();
+                }
+                MenuOption::Quit => {
+                    app_exit_events.send(AppExit);
+                    current.index = 0;
+                }
             }
         }
     }
 }
+
+pub fn update_difficulty_label(
+    difficulty: Res<DifficultyConfig>,
+    mut query: Query<&mut Text, With<DifficultyButton>>,
+) {
+    if !difficulty.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.value = difficulty_label(difficulty.current);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{pause_game, prelude::*};
+
+pub const MELEE_COOLDOWN_SEC: f32 = 0.6;
+pub const MELEE_FIRST_FRAME: usize = 5;
+pub const MELEE_LAST_FRAME: usize = 6;
+pub const MELEE_HITBOX_OFFSET: Vec2 = Vec2::new(0.0, -30.0);
+pub const MELEE_DAMAGE: u32 = 2;
+
+/// Enemies already hit by a slash so each one only takes damage once per swing
+#[derive(Component, Default)]
+pub struct SlashedEnemies(pub Vec<Entity>);
+
+#[derive(Bundle)]
+pub struct MeleeSlashBundle {
+    melee_slash: MeleeSlash,
+    slashed_enemies: SlashedEnemies,
+    hitbox: HitBox,
+    collision_layers: CollisionLayers,
+    transform_bundle: TransformBundle,
+}
+
+impl MeleeSlashBundle {
+    pub fn new(player_pos: Vec3) -> Self {
+        MeleeSlashBundle {
+            melee_slash: MeleeSlash,
+            slashed_enemies: SlashedEnemies::default(),
+            hitbox: HitBox(Vec2::new(56.0, 36.0)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::PlayerProjectile,
+                &[CollisionLayer::Enemy],
+            ),
+            transform_bundle: TransformBundle::from_transform(Transform::from_translation(
+                player_pos + MELEE_HITBOX_OFFSET.extend(0.0),
+            )),
+        }
+    }
+}
+
+impl AttackCooldown {
+    /// Creates a cooldown that can be used straight away
+    pub fn ready(duration_sec: f32) -> Self {
+        let mut timer = Timer::from_seconds(duration_sec, TimerMode::Once);
+        timer.tick(timer.duration());
+
+        AttackCooldown(timer)
+    }
+}
+
+pub struct MeleePlugin;
+impl Plugin for MeleePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_run_criteria(pause_game)
+                .with_system(player_melee_system)
+                .with_system(melee_slash_follow),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_system(melee_hit_enemy.after(detect_collisions)),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::EndStage).with_system(despawner::<MeleeSlash>),
+        );
+    }
+}
+
+pub fn player_melee_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut query: Query<(
+        Entity,
+        &mut Player,
+        &mut AttackCooldown,
+        &mut SlashingAnimationTimer,
+        &Transform,
+        &mut TextureAtlasSprite,
+        &PlayerSlot,
+    )>,
+    mut transition_events: EventWriter<PlayerTransitionEvent>,
+) {
+    for (
+        entity,
+        mut player,
+        mut attack_cooldown,
+        mut slashing_animation_timer,
+        transform,
+        mut sprite,
+        slot,
+    ) in query.iter_mut()
+    {
+        attack_cooldown.0.tick(time.delta());
+
+        if keyboard_input.any_just_pressed(slot.controls().melee)
+            && attack_cooldown.0.finished()
+            && player.transition_to(entity, PlayerAction::Slashing, &mut transition_events)
+        {
+            attack_cooldown.0.reset();
+            commands.spawn((MeleeSlashBundle::new(transform.translation), Owner(entity)));
+            continue;
+        }
+
+        if player.0 != PlayerAction::Slashing {
+            continue;
+        }
+
+        if slashing_animation_timer.tick(time.delta()).just_finished() {
+            if sprite.index < MELEE_LAST_FRAME {
+                sprite.index += 1;
+            } else {
+                player.transition_to(entity, PlayerAction::Falling, &mut transition_events);
+            }
+        }
+    }
+}
+
+/// Keeps each slash hitbox in front of the player who swung it and removes it once the slash
+/// is over
+pub fn melee_slash_follow(
+    mut commands: Commands,
+    player_query: Query<(&Player, &Transform), Without<MeleeSlash>>,
+    mut slash_query: Query<
+        (Entity, &mut Transform, &Owner),
+        (With<MeleeSlash>, Without<MarkDespawn>),
+    >,
+) {
+    for (entity, mut transform, owner) in slash_query.iter_mut() {
+        let (player, player_transform) = match player_query.get(owner.0) {
+            Ok(player) => player,
+            Err(_) => {
+                commands.entity(entity).insert(MarkDespawn);
+                continue;
+            }
+        };
+
+        if player.0 != PlayerAction::Slashing {
+            commands.entity(entity).insert(MarkDespawn);
+            continue;
+        }
+
+        transform.translation = player_transform.translation + MELEE_HITBOX_OFFSET.extend(0.0);
+    }
+}
+
+/// Melee slashes cut through every enemy they touch and are never reflected
+pub fn melee_hit_enemy(
+    mut projectile_hit_enemy_events: EventReader<ProjectileHitEnemyEvent>,
+    mut slash_query: Query<
+        (&Transform, &mut SlashedEnemies, Option<&Owner>),
+        (With<MeleeSlash>, Without<MarkDespawn>),
+    >,
+    mut enemy_query: Query<(&Transform, &mut Enemy, &EnemyType, &mut Health), Without<MarkDespawn>>,
+    mut sfx_events: EventWriter<SFXEvents>,
+    mut enemy_hit_events: EventWriter<EnemyHitEvent>,
+    mut enemy_killed_events: EventWriter<EnemyKilledEvent>,
+) {
+    for event in projectile_hit_enemy_events.iter() {
+        let (slash_transform, mut slashed_enemies, owner) =
+            match slash_query.get_mut(event.projectile) {
+                Ok(slash) => slash,
+                Err(_) => continue,
+            };
+
+        let (enemy_transform, mut enemy, enemy_type, mut health) =
+            match enemy_query.get_mut(event.enemy) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+
+        if enemy.0 == EnemyState::Dead || slashed_enemies.0.contains(&event.enemy) {
+            continue;
+        }
+
+        slashed_enemies.0.push(event.enemy);
+
+        if !health.damage(MELEE_DAMAGE) {
+            enemy_hit_events.send(EnemyHitEvent {
+                entity: event.enemy,
+                knockback: (enemy_transform.translation - slash_transform.translation).truncate(),
+            });
+            continue;
+        }
+
+        sfx_events.send(SFXEvents::CollisionSound);
+
+        enemy.0 = EnemyState::Dead;
+        enemy_killed_events.send(EnemyKilledEvent {
+            entity: event.enemy,
+            enemy_type: *enemy_type,
+            position: enemy_transform.translation,
+            killer: owner.map(|owner| owner.0),
+        });
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{pause_game, prelude::*};
+
+pub const NINJUTSU_FLASH_SEC: f32 = 0.6;
+
+#[derive(Component, Debug)]
+pub struct NinjutsuCharges(pub u32);
+
+#[derive(Component, Deref, DerefMut)]
+pub struct NinjutsuFlash(pub Timer);
+
+pub struct NinjutsuPlugin;
+impl Plugin for NinjutsuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_run_criteria(pause_game)
+                .with_system(player_ninjutsu_system)
+                .with_system(ninjutsu_flash_fadeout),
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::EndStage).with_system(despawner::<NinjutsuFlash>),
+        );
+    }
+}
+
+/// Whether a position is inside the part of the world a camera at `camera_translation` shows
+pub fn is_on_screen(translation: Vec3, camera_translation: Vec3) -> bool {
+    let half_visible_height = (WORLD_HEIGHT / 2.0) * CAMERA_SCALE;
+    let offset = translation - camera_translation;
+
+    offset.x.abs() <= WORLD_WIDTH / 2.0 && offset.y.abs() <= half_visible_height
+}
+
+pub fn player_ninjutsu_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    camera_query: Query<&Transform, With<Camera>>,
+    mut player_query: Query<(Entity, &Player, &mut NinjutsuCharges, &PlayerSlot)>,
+    mut enemy_query: Query<(Entity, &Transform, &mut Enemy, &EnemyType), Without<MarkDespawn>>,
+    mut sfx_events: EventWriter<SFXEvents>,
+    mut enemy_killed_events: EventWriter<EnemyKilledEvent>,
+) {
+    let camera_translation = camera_query
+        .get_single()
+        .map_or(Vec3::ZERO, |camera_transform| camera_transform.translation);
+
+    for (player_entity, player, mut charges, slot) in player_query.iter_mut() {
+        if !keyboard_input.any_just_pressed(slot.controls().ninjutsu)
+            || charges.0 == 0
+            || player.1 != LevelState::Start
+        {
+            continue;
+        }
+
+        charges.0 -= 1;
+        sfx_events.send(SFXEvents::NinjutsuSound);
+        spawn_ninjutsu_flash(&mut commands, camera_translation);
+
+        for (enemy_entity, enemy_transform, mut enemy, enemy_type) in enemy_query.iter_mut() {
+            if enemy.0 == EnemyState::Dead
+                || !is_on_screen(enemy_transform.translation, camera_translation)
+            {
+                continue;
+            }
+
+            enemy.0 = EnemyState::Dead;
+            enemy_killed_events.send(EnemyKilledEvent {
+                entity: enemy_entity,
+                enemy_type: *enemy_type,
+                position: enemy_transform.translation,
+                killer: Some(player_entity),
+            });
+        }
+    }
+}
+
+/// Covers the screen of a camera at `camera_translation` in a white flash
+fn spawn_ninjutsu_flash(commands: &mut Commands, camera_translation: Vec3) {
+    let flash_size = Vec2::new(WORLD_WIDTH * 2.0, WORLD_HEIGHT);
+
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(camera_translation.truncate().extend(3.0)),
+            ..Default::default()
+        },
+        Sprite {
+            color: Color::rgba(1.0, 1.0, 1.0, 1.0),
+            custom_size: Some(flash_size),
+            ..Default::default()
+        }
+        .ease_to(
+            Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                custom_size: Some(flash_size),
+                ..Default::default()
+            },
+            EaseFunction::QuadraticOut,
+            EasingType::Once {
+                duration: Duration::from_secs_f32(NINJUTSU_FLASH_SEC),
+            },
+        ),
+        NinjutsuFlash(Timer::from_seconds(NINJUTSU_FLASH_SEC, TimerMode::Once)),
+    ));
+}
+
+pub fn ninjutsu_flash_fadeout(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut NinjutsuFlash), Without<MarkDespawn>>,
+) {
+    for (entity, mut flash) in query.iter_mut() {
+        if flash.tick(time.delta()).finished() {
+            commands.entity(entity).insert(MarkDespawn);
+        }
+    }
+}
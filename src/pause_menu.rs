@@ -4,6 +4,7 @@ pub struct PauseMenuPlugin;
 impl Plugin for PauseMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_setup))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(pause_menu_quit))
             .add_system_set(
                 SystemSet::on_exit(GameState::Paused).with_system(despawner::<OnPauseScreen>),
             )
@@ -20,6 +21,7 @@ fn pause_setup(mut commands: Commands, game_assets: Res<GameAssets>) {
             NodeBundle {
                 style: Style {
                     align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
                     justify_content: JustifyContent::Center,
                     size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                     ..default()
@@ -37,9 +39,39 @@ fn pause_setup(mut commands: Commands, game_assets: Res<GameAssets>) {
                     color: Color::WHITE,
                 },
             ));
+            commands.spawn(TextBundle::from_section(
+                "Q TO QUIT",
+                TextStyle {
+                    font: game_assets.menu_font.clone(),
+                    font_size: 12.0,
+                    color: Color::WHITE,
+                },
+            ));
         });
 }
 
+/// Ends the run from the pause screen, going through `EndStage` so the stage gets cleaned up
+fn pause_menu_quit(
+    mut time: ResMut<Time>,
+    mut game_state: ResMut<State<GameState>>,
+    mut game_script_state: ResMut<GameScriptState>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut pause_event: ResMut<PauseEvent>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Q) {
+        return;
+    }
+
+    // also done when leaving `InGame`, but the end of stage screen shouldn't be slowed down
+    time.set_relative_speed(1.0);
+    game_script_state.quit = true;
+    keyboard_input.reset(KeyCode::Q);
+    *pause_event = PauseEvent::Unpaused;
+
+    // leaves both the pause screen and the stage underneath it
+    let _ = game_state.replace(GameState::EndStage);
+}
+
 fn pause_game(
     mut game_state: ResMut<State<GameState>>,
     mut keyboard_input: ResMut<Input<KeyCode>>,
@@ -0,0 +1,709 @@
+use std::collections::HashMap;
+
+use crate::prelude::*;
+use ron::de::from_bytes;
+use serde::Deserialize;
+
+pub const PLATFORM_GRAVITY: f32 = 1400.0;
+pub const PLATFORM_JUMP_SPEED: f32 = 520.0;
+pub const PLATFORM_THROW_COOLDOWN_SEC: f32 = 0.3;
+pub const PLATFORM_SHURIKEN_SPEED: f32 = 400.0;
+pub const PLATFORM_ENEMY_SPEED: f32 = 60.0;
+pub const PLATFORM_KNOCKBACK: Vec2 = Vec2::new(160.0, 220.0);
+/// Half of the width the camera shows, used to keep it inside the level
+pub const PLATFORM_CAMERA_HALF_WIDTH: f32 = WORLD_WIDTH * CAMERA_SCALE / 2.0;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Platform {
+    /// Center of the platform
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    /// Hidden platforms still block movement, used for colliders drawn by a tile layer
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlatformEnemy {
+    pub position: (f32, f32),
+    /// Distance the enemy walks to each side of where it spawned
+    pub patrol: f32,
+    #[serde(default)]
+    pub enemy_type: EnemyType,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlatformPickup {
+    pub position: (f32, f32),
+    /// Score awarded when the player picks it up
+    pub value: u32,
+}
+
+/// Layout of a side-scrolling stage, x runs from 0 to `width`
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlatformLevel {
+    pub width: f32,
+    pub spawn: (f32, f32),
+    /// Reaching this x clears the stage
+    pub exit_x: f32,
+    pub platforms: Vec<Platform>,
+    pub enemies: Vec<PlatformEnemy>,
+    #[serde(default)]
+    pub pickups: Vec<PlatformPickup>,
+    /// Time bombs that all have to be defused before the exit opens
+    #[serde(default)]
+    pub bombs: Vec<(f32, f32)>,
+}
+
+/// Every platforming level from `data/platforming.ron` keyed by the name used in the game script
+#[derive(Resource, Debug)]
+pub struct PlatformLevels(pub HashMap<String, PlatformLevel>);
+
+/// Level currently being played
+#[derive(Resource, Debug)]
+pub struct ActivePlatformLevel(pub PlatformLevel);
+
+#[derive(Component, Debug)]
+pub struct PlatformPlayer {
+    pub on_ground: bool,
+    /// 1.0 when facing right and -1.0 when facing left
+    pub facing: f32,
+}
+
+#[derive(Component, Debug)]
+pub struct Solid;
+
+/// Downward acceleration of a platforming body in units per second squared
+///
+/// Kept apart from `Gravity`, which the bonus stage applies once per frame.
+#[derive(Component, Debug)]
+pub struct PlatformGravity(pub f32);
+
+#[derive(Component, Debug)]
+pub struct ScorePickup(pub u32);
+
+#[derive(Component, Debug)]
+pub struct GroundEnemy {
+    pub home_x: f32,
+    pub patrol: f32,
+    /// Shielded enemies send back shurikens thrown at their front unless they are stunned
+    pub shielded: bool,
+    pub enemy_type: EnemyType,
+}
+
+#[derive(Bundle)]
+pub struct PlatformPlayerBundle {
+    platform_player: PlatformPlayer,
+    slot: PlayerSlot,
+    walking_animation_timer: WalkingAnimationTimer,
+    attack_cooldown: AttackCooldown,
+    gravity: PlatformGravity,
+    velocity: Velocity,
+    hitbox: HitBox,
+    collision_layers: CollisionLayers,
+    sprite_bundle: SpriteSheetBundle,
+}
+
+impl PlatformPlayerBundle {
+    pub fn new(game_assets: &Res<GameAssets>, position: Vec2, slot: PlayerSlot) -> Self {
+        PlatformPlayerBundle {
+            platform_player: PlatformPlayer {
+                on_ground: false,
+                facing: 1.0,
+            },
+            slot,
+            walking_animation_timer: WalkingAnimationTimer(Timer::from_seconds(
+                0.2,
+                TimerMode::Repeating,
+            )),
+            attack_cooldown: AttackCooldown::ready(PLATFORM_THROW_COOLDOWN_SEC),
+            gravity: PlatformGravity(PLATFORM_GRAVITY),
+            velocity: Velocity(Vec2::ZERO),
+            hitbox: HitBox(Vec2::new(20.0, 40.0)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Player,
+                &[CollisionLayer::Pickup, CollisionLayer::Enemy],
+            ),
+            sprite_bundle: SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(7),
+                texture_atlas: game_assets.musashi.clone(),
+                transform: Transform::from_translation(position.extend(2.0)),
+                ..default()
+            },
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct PlatformBundle {
+    solid: Solid,
+    hitbox: HitBox,
+    sprite_bundle: SpriteBundle,
+}
+
+impl PlatformBundle {
+    pub fn new(platform: &Platform) -> Self {
+        let size = Vec2::new(platform.size.0, platform.size.1);
+
+        PlatformBundle {
+            solid: Solid,
+            hitbox: HitBox(size),
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.35, 0.25, 0.2),
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_xyz(platform.position.0, platform.position.1, 1.0),
+                visibility: Visibility {
+                    is_visible: !platform.hidden,
+                },
+                ..default()
+            },
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct GroundEnemyBundle {
+    ground_enemy: GroundEnemy,
+    health: Health,
+    gravity: PlatformGravity,
+    velocity: Velocity,
+    hitbox: HitBox,
+    collision_layers: CollisionLayers,
+    sprite_bundle: SpriteSheetBundle,
+}
+
+impl GroundEnemyBundle {
+    pub fn new(
+        game_assets: &Res<GameAssets>,
+        enemy: &PlatformEnemy,
+        enemy_config: &EnemyConfig,
+    ) -> Self {
+        GroundEnemyBundle {
+            ground_enemy: GroundEnemy {
+                home_x: enemy.position.0,
+                patrol: enemy.patrol,
+                shielded: enemy.enemy_type == EnemyType::ShieldNinja,
+                enemy_type: enemy.enemy_type,
+            },
+            health: Health(enemy_config.settings(enemy.enemy_type).health.max(1)),
+            gravity: PlatformGravity(PLATFORM_GRAVITY),
+            velocity: Velocity(Vec2::new(PLATFORM_ENEMY_SPEED, 0.0)),
+            hitbox: HitBox(Vec2::new(30.0, 50.0)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Enemy,
+                &[CollisionLayer::PlayerProjectile, CollisionLayer::Player],
+            ),
+            sprite_bundle: SpriteSheetBundle {
+                texture_atlas: enemy.enemy_type.get_texture(game_assets),
+                transform: Transform::from_xyz(enemy.position.0, enemy.position.1, 1.5),
+                ..default()
+            },
+        }
+    }
+}
+
+pub struct PlatformingPlugin;
+impl Plugin for PlatformingPlugin {
+    fn build(&self, app: &mut App) {
+        let levels: HashMap<String, PlatformLevel> =
+            from_bytes(include_bytes!("../data/platforming.ron")).unwrap();
+
+        app.insert_resource(PlatformLevels(levels))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Platforming).with_system(spawn_platform_level),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Platforming)
+                    .with_system(platform_player_controls)
+                    .with_system(platform_physics.after(platform_player_controls))
+                    .with_system(platform_player_animation.after(platform_physics))
+                    .with_system(platform_throwing)
+                    .with_system(platform_projectile_movement)
+                    .with_system(shuriken_animator)
+                    .with_system(reflect_projectile)
+                    .with_system(ground_enemy_patrol)
+                    .with_system(ground_enemy_collision.after(detect_collisions))
+                    .with_system(enemy_hit_system.after(ground_enemy_collision))
+                    .with_system(enemy_death_system.after(ground_enemy_collision))
+                    .with_system(ground_enemy_contact.after(detect_collisions))
+                    .with_system(score_pickup_collection.after(detect_collisions))
+                    .with_system(hit_flash_system)
+                    .with_system(death_effect_animator)
+                    .with_system(platform_camera_follow.after(platform_physics))
+                    .with_system(platform_stage_exit),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Platforming).with_system(reset_camera))
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage)
+                    .with_system(despawner::<PlatformPlayer>)
+                    .with_system(despawner::<Solid>)
+                    .with_system(despawner::<GroundEnemy>)
+                    .with_system(despawner::<ScorePickup>),
+            );
+    }
+}
+
+/// Whether two boxes given by their centers and sizes overlap
+pub fn overlaps(a: Vec2, a_size: Vec2, b: Vec2, b_size: Vec2) -> bool {
+    let distance = (a - b).abs();
+    let reach = (a_size + b_size) / 2.0;
+
+    distance.x < reach.x && distance.y < reach.y
+}
+
+pub fn spawn_platform_level(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_script_state: Res<GameScriptState>,
+    platform_levels: Res<PlatformLevels>,
+    tiled_maps: Res<Assets<TiledMap>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    enemy_config: Res<EnemyConfig>,
+    mut stage_objective: ResMut<StageObjective>,
+) {
+    let level = match &game_script_state.current().script {
+        GameScript::Platforming { level } => match platform_levels.0.get(level) {
+            Some(level) => level,
+            None => panic!(
+                "No platforming level named {:?} in data/platforming.ron",
+                level
+            ),
+        },
+        GameScript::TiledStage { map } => {
+            match game_assets
+                .tiled_maps
+                .get(map)
+                .and_then(|tiled_map| tiled_maps.get(tiled_map))
+            {
+                Some(tiled_map) => {
+                    tiled_map.spawn_tiles(&mut commands, &mut texture_atlases);
+                    &tiled_map.level
+                }
+                None => panic!("Tiled map {:?} was not loaded", map),
+            }
+        }
+        script => panic!("{:?} is not a platforming stage", script),
+    };
+
+    for platform in level.platforms.iter() {
+        commands.spawn(PlatformBundle::new(platform));
+    }
+
+    for enemy in level.enemies.iter() {
+        commands.spawn(GroundEnemyBundle::new(&game_assets, enemy, &enemy_config));
+    }
+
+    for pickup in level.pickups.iter() {
+        commands.spawn((
+            ScorePickup(pickup.value),
+            HitBox(Vec2::new(16.0, 16.0)),
+            CollisionLayers::new(CollisionLayer::Pickup, &[CollisionLayer::Player]),
+            SpriteBundle {
+                texture: game_assets.power_up.clone(),
+                transform: Transform::from_xyz(pickup.position.0, pickup.position.1, 1.5),
+                ..default()
+            },
+        ));
+    }
+
+    for bomb in level.bombs.iter() {
+        commands.spawn(BombBundle::new(&game_assets, Vec2::new(bomb.0, bomb.1)));
+    }
+    *stage_objective = StageObjective::new(level.bombs.len());
+
+    commands.spawn(PlatformPlayerBundle::new(
+        &game_assets,
+        Vec2::new(level.spawn.0, level.spawn.1),
+        PlayerSlot::One,
+    ));
+    commands.insert_resource(ActivePlatformLevel(level.clone()));
+}
+
+pub fn platform_player_controls(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut query: Query<(&mut PlatformPlayer, &mut Velocity, &PlayerSlot)>,
+) {
+    for (mut platform_player, mut velocity, slot) in query.iter_mut() {
+        let controls = slot.controls();
+        velocity.x = 0.0;
+
+        if keyboard_input.any_pressed(controls.left) {
+            velocity.x = -PLAYER_WALKING_SPEED;
+            platform_player.facing = -1.0;
+        }
+
+        if keyboard_input.any_pressed(controls.right) {
+            velocity.x = PLAYER_WALKING_SPEED;
+            platform_player.facing = 1.0;
+        }
+
+        // flipping off the walls becomes jumping off the ground
+        if keyboard_input.any_just_pressed(controls.flip.into_iter().chain(controls.up))
+            && platform_player.on_ground
+        {
+            velocity.y = PLATFORM_JUMP_SPEED;
+            platform_player.on_ground = false;
+        }
+    }
+}
+
+/// Applies gravity and keeps the player and ground enemies on top of solid platforms
+pub fn platform_physics(
+    mut commands: Commands,
+    time: Res<Time>,
+    level: Res<ActivePlatformLevel>,
+    solid_query: Query<(&Transform, &HitBox), With<Solid>>,
+    mut body_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &PlatformGravity,
+            &HitBox,
+            Option<&mut PlatformPlayer>,
+        ),
+        Without<Solid>,
+    >,
+) {
+    for (entity, mut transform, mut velocity, gravity, hitbox, platform_player) in
+        body_query.iter_mut()
+    {
+        velocity.y -= gravity.0 * time.delta_seconds();
+
+        let mut position = transform.translation.truncate();
+        let mut on_ground = false;
+
+        // horizontal and vertical moves are resolved separately so walls and floors don't snag
+        position.x += velocity.x * time.delta_seconds();
+        for (solid_transform, solid_hitbox) in solid_query.iter() {
+            let solid = solid_transform.translation.truncate();
+            if overlaps(position, hitbox.0, solid, solid_hitbox.0) {
+                let side = (position.x - solid.x).signum();
+                position.x = solid.x + side * (hitbox.0.x + solid_hitbox.0.x) / 2.0;
+            }
+        }
+
+        position.y += velocity.y * time.delta_seconds();
+        for (solid_transform, solid_hitbox) in solid_query.iter() {
+            let solid = solid_transform.translation.truncate();
+            if overlaps(position, hitbox.0, solid, solid_hitbox.0) {
+                let side = (position.y - solid.y).signum();
+                position.y = solid.y + side * (hitbox.0.y + solid_hitbox.0.y) / 2.0;
+                on_ground |= side > 0.0;
+                velocity.y = 0.0;
+            }
+        }
+
+        position.x = position
+            .x
+            .clamp(hitbox.0.x / 2.0, level.0.width - hitbox.0.x / 2.0);
+
+        // falling off the level puts the player back at the start, anything else is gone for good
+        if position.y < -WORLD_HEIGHT / 2.0 {
+            if platform_player.is_none() {
+                commands.entity(entity).insert(MarkDespawn);
+                continue;
+            }
+
+            position = Vec2::new(level.0.spawn.0, level.0.spawn.1);
+            velocity.0 = Vec2::ZERO;
+        }
+
+        transform.translation = position.extend(transform.translation.z);
+
+        if let Some(mut platform_player) = platform_player {
+            platform_player.on_ground = on_ground;
+        }
+    }
+}
+
+/// Reuses the idle, walking and flipping frames of the bonus stage
+pub fn platform_player_animation(
+    time: Res<Time>,
+    mut query: Query<(
+        &PlatformPlayer,
+        &Velocity,
+        &mut WalkingAnimationTimer,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    for (platform_player, velocity, mut walking_animation_timer, mut sprite) in query.iter_mut() {
+        sprite.flip_x = platform_player.facing < 0.0;
+
+        if !platform_player.on_ground {
+            sprite.index = 14;
+        } else if velocity.x == 0.0 {
+            sprite.index = 7;
+        } else {
+            if walking_animation_timer.0.tick(time.delta()).just_finished() {
+                sprite.index += 1;
+            }
+
+            if sprite.index > 13 || sprite.index < 8 {
+                sprite.index = 8;
+            }
+        }
+    }
+}
+
+pub fn platform_throwing(
+    mut commands: Commands,
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    game_assets: Res<GameAssets>,
+    mut query: Query<(
+        &PlatformPlayer,
+        &Transform,
+        &mut AttackCooldown,
+        &PlayerSlot,
+    )>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for (platform_player, transform, mut attack_cooldown, slot) in query.iter_mut() {
+        attack_cooldown.0.tick(time.delta());
+
+        if keyboard_input.any_pressed(slot.controls().attack) && attack_cooldown.0.finished() {
+            let direction = Vec2::new(platform_player.facing, 0.0);
+
+            attack_cooldown.0.reset();
+            commands.spawn(ShurikenBundle::new(
+                &game_assets,
+                transform.translation + (direction * 20.0).extend(0.0),
+                direction,
+                PLATFORM_SHURIKEN_SPEED,
+            ));
+            sfx_events.send(SFXEvents::ShurikenSound);
+        }
+    }
+}
+
+pub fn platform_projectile_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    camera_query: Query<&Transform, (With<Camera>, Without<Shuriken>)>,
+    mut query: Query<
+        (Entity, &mut Transform, &Velocity, &mut PreviousPosition),
+        (With<Shuriken>, Without<MarkDespawn>),
+    >,
+) {
+    let camera_x = camera_query
+        .get_single()
+        .map_or(0.0, |camera_transform| camera_transform.translation.x);
+
+    for (entity, mut transform, velocity, mut previous_position) in query.iter_mut() {
+        previous_position.0 = transform.translation.truncate();
+        transform.translation += (velocity.0 * time.delta_seconds()).extend(0.0);
+
+        if (transform.translation.x - camera_x).abs() > WORLD_WIDTH {
+            commands.entity(entity).insert(MarkDespawn);
+        }
+    }
+}
+
+pub fn ground_enemy_patrol(
+    mut query: Query<(
+        &Transform,
+        &mut Velocity,
+        &mut TextureAtlasSprite,
+        &GroundEnemy,
+        Option<&Stunned>,
+    )>,
+) {
+    for (transform, mut velocity, mut sprite, ground_enemy, stunned) in query.iter_mut() {
+        if stunned.is_some() {
+            velocity.x = 0.0;
+            continue;
+        }
+
+        let offset = transform.translation.x - ground_enemy.home_x;
+
+        if offset > ground_enemy.patrol {
+            velocity.x = -PLATFORM_ENEMY_SPEED;
+        } else if offset < -ground_enemy.patrol || velocity.x == 0.0 {
+            velocity.x = PLATFORM_ENEMY_SPEED;
+        }
+
+        sprite.index = if velocity.x < 0.0 { 1 } else { 0 };
+    }
+}
+
+/// Shurikens damage ground enemies, shielded ones send back the shurikens reaching their front
+///
+/// Blocked shurikens, non lethal hits and kills are handed to `reflect_projectile`,
+/// `enemy_hit_system` and `enemy_death_system`, the same as in the bonus stage.
+pub fn ground_enemy_collision(
+    mut commands: Commands,
+    mut projectile_hit_enemy_events: EventReader<ProjectileHitEnemyEvent>,
+    shuriken_query: Query<&Velocity, (With<Shuriken>, Without<MarkDespawn>, Without<Reflected>)>,
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &Velocity,
+            &GroundEnemy,
+            &mut Health,
+            Option<&Stunned>,
+        ),
+        (Without<Shuriken>, Without<MarkDespawn>),
+    >,
+    mut sfx_events: EventWriter<SFXEvents>,
+    mut enemy_hit_events: EventWriter<EnemyHitEvent>,
+    mut enemy_killed_events: EventWriter<EnemyKilledEvent>,
+) {
+    // every shuriken stops at the first enemy it reaches
+    let mut spent_shurikens = Vec::new();
+
+    for event in projectile_hit_enemy_events.iter() {
+        if spent_shurikens.contains(&event.projectile) {
+            continue;
+        }
+
+        let shuriken_velocity = match shuriken_query.get(event.projectile) {
+            Ok(shuriken_velocity) => shuriken_velocity,
+            Err(_) => continue,
+        };
+
+        let (enemy_transform, enemy_velocity, ground_enemy, mut health, stunned) =
+            match enemy_query.get_mut(event.enemy) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+
+        if health.0 == 0 {
+            continue;
+        }
+
+        spent_shurikens.push(event.projectile);
+
+        // enemies face the way they walk
+        let blocked = ground_enemy.shielded
+            && stunned.is_none()
+            && shuriken_velocity.x * enemy_velocity.x < 0.0;
+        if blocked {
+            commands
+                .entity(event.projectile)
+                .insert(Reflected(shuriken_velocity.y.atan2(-shuriken_velocity.x)));
+            sfx_events.send(SFXEvents::ReflectionSound);
+            continue;
+        }
+
+        commands.entity(event.projectile).insert(MarkDespawn);
+
+        if health.damage(SHURIKEN_DAMAGE) {
+            enemy_killed_events.send(EnemyKilledEvent {
+                entity: event.enemy,
+                enemy_type: ground_enemy.enemy_type,
+                position: enemy_transform.translation,
+                killer: None,
+            });
+        } else {
+            enemy_hit_events.send(EnemyHitEvent {
+                entity: event.enemy,
+                knockback: shuriken_velocity.0,
+            });
+        }
+    }
+}
+
+/// Touching a ground enemy knocks the player back and away from it
+pub fn ground_enemy_contact(
+    mut enemy_touched_player_events: EventReader<EnemyTouchedPlayerEvent>,
+    mut player_query: Query<(&Transform, &mut Velocity, &mut PlatformPlayer)>,
+    enemy_query: Query<&Transform, (With<GroundEnemy>, Without<PlatformPlayer>)>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    // a player touching several enemies is only knocked back by the first one
+    let mut knocked_back = Vec::new();
+
+    for event in enemy_touched_player_events.iter() {
+        if knocked_back.contains(&event.player) {
+            continue;
+        }
+
+        let (player_transform, mut velocity, mut platform_player) =
+            match player_query.get_mut(event.player) {
+                Ok(player) => player,
+                Err(_) => continue,
+            };
+        let enemy_transform = match enemy_query.get(event.enemy) {
+            Ok(enemy_transform) => enemy_transform,
+            Err(_) => continue,
+        };
+
+        let side = (player_transform.translation.x - enemy_transform.translation.x).signum();
+        velocity.0 = Vec2::new(side * PLATFORM_KNOCKBACK.x, PLATFORM_KNOCKBACK.y);
+        platform_player.on_ground = false;
+        sfx_events.send(SFXEvents::CollisionSound);
+        knocked_back.push(event.player);
+    }
+}
+
+pub fn score_pickup_collection(
+    mut commands: Commands,
+    mut player_touched_pickup_events: EventReader<PlayerTouchedPickupEvent>,
+    mut score: ResMut<Score>,
+    pickup_query: Query<&ScorePickup, Without<MarkDespawn>>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for event in player_touched_pickup_events.iter() {
+        let score_pickup = match pickup_query.get(event.pickup) {
+            Ok(score_pickup) => score_pickup,
+            Err(_) => continue,
+        };
+
+        commands.entity(event.pickup).insert(MarkDespawn);
+        score.0 += score_pickup.0;
+        sfx_events.send(SFXEvents::PickupSound);
+    }
+}
+
+pub fn platform_camera_follow(
+    level: Res<ActivePlatformLevel>,
+    player_query: Query<&Transform, With<PlatformPlayer>>,
+    mut camera_query: Query<&mut Transform, (With<Camera>, Without<PlatformPlayer>)>,
+) {
+    // several players share the view by keeping the camera between them
+    let player_count = player_query.iter().count();
+    if player_count == 0 {
+        return;
+    }
+
+    let player_x = player_query
+        .iter()
+        .map(|player_transform| player_transform.translation.x)
+        .sum::<f32>()
+        / player_count as f32;
+    let max_x = (level.0.width - PLATFORM_CAMERA_HALF_WIDTH).max(PLATFORM_CAMERA_HALF_WIDTH);
+
+    for mut camera_transform in camera_query.iter_mut() {
+        camera_transform.translation.x = player_x.clamp(PLATFORM_CAMERA_HALF_WIDTH, max_x);
+    }
+}
+
+/// Clears the stage once the player reaches the exit, which stays locked while bombs are left
+pub fn platform_stage_exit(
+    level: Res<ActivePlatformLevel>,
+    stage_objective: Res<StageObjective>,
+    player_query: Query<&Transform, With<PlatformPlayer>>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if !stage_objective.is_complete() {
+        return;
+    }
+
+    for player_transform in player_query.iter() {
+        if player_transform.translation.x >= level.0.exit_x {
+            end_stage(&mut game_state);
+            return;
+        }
+    }
+}
+
+pub fn reset_camera(mut camera_query: Query<&mut Transform, With<Camera>>) {
+    for mut camera_transform in camera_query.iter_mut() {
+        camera_transform.translation.x = 0.0;
+        camera_transform.translation.y = 0.0;
+    }
+}
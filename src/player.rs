@@ -7,25 +7,47 @@ pub const PLAYER_FLIPPING_SPEED: f32 = 250.0;
 pub const FINAL_PLAYER_POS_Y: f32 = -100.0;
 pub const BONUS_STAGE_ENDING_PLAYER_STARTING_POS_X: f32 = 0.0;
 pub const BONUS_STAGE_ENDING_PLAYER_STARTING_POS_Y: f32 = 100.0;
+/// Hits the player can take over a whole story run
+pub const STORY_LIVES: u32 = 3;
+
+/// Sent when something harmful touches a player
+pub struct PlayerHitEvent {
+    pub entity: Entity,
+}
 
 #[derive(Bundle)]
 pub struct PlayerBundle {
     player: Player,
+    slot: PlayerSlot,
     attacking_timer: AttackingTimer,
+    attack_cooldown: AttackCooldown,
+    rapid_fire_timer: RapidFireTimer,
+    ninjutsu_charges: NinjutsuCharges,
+    active_power_ups: ActivePowerUps,
     sprite_bundle: SpriteSheetBundle,
     walking_animation_timer: WalkingAnimationTimer,
     flipping_animation_timer: FlippingAnimationTimer,
+    slashing_animation_timer: SlashingAnimationTimer,
     dimensions: Dimensions,
     gravity: Gravity,
     velocity: Velocity,
     hitbox: HitBox,
+    collision_layers: CollisionLayers,
 }
 
 impl PlayerBundle {
-    pub fn new(game_assets: Res<GameAssets>) -> Self {
+    pub fn new(game_assets: &Res<GameAssets>, ninjutsu_charges: u32, slot: PlayerSlot) -> Self {
         PlayerBundle {
             player: Player(PlayerAction::Idle, LevelState::Intro),
+            slot,
             attacking_timer: AttackingTimer(Timer::from_seconds(0.025, TimerMode::Repeating)),
+            attack_cooldown: AttackCooldown::ready(MELEE_COOLDOWN_SEC),
+            rapid_fire_timer: RapidFireTimer(Timer::from_seconds(
+                RAPID_FIRE_INTERVAL_SEC,
+                TimerMode::Repeating,
+            )),
+            ninjutsu_charges: NinjutsuCharges(ninjutsu_charges),
+            active_power_ups: ActivePowerUps::default(),
             walking_animation_timer: WalkingAnimationTimer(Timer::from_seconds(
                 0.2,
                 TimerMode::Repeating,
@@ -34,10 +56,23 @@ impl PlayerBundle {
                 0.1,
                 TimerMode::Repeating,
             )),
+            slashing_animation_timer: SlashingAnimationTimer(Timer::from_seconds(
+                0.08,
+                TimerMode::Repeating,
+            )),
             sprite_bundle: SpriteSheetBundle {
+                sprite: TextureAtlasSprite {
+                    index: 7,
+                    color: slot.tint(),
+                    ..default()
+                },
                 texture_atlas: game_assets.musashi.clone(),
                 transform: Transform {
-                    translation: Vec3::new(BONUS_STAGE_SPAWN_POS.x, BONUS_STAGE_SPAWN_POS.y, 2.0),
+                    translation: Vec3::new(
+                        BONUS_STAGE_SPAWN_POS.x + slot.offset_x(),
+                        BONUS_STAGE_SPAWN_POS.y,
+                        2.0,
+                    ),
                     ..default()
                 },
                 ..default()
@@ -46,101 +81,149 @@ impl PlayerBundle {
             gravity: Gravity(1.75),
             velocity: Velocity(Vec2::new(0.0, PLAYER_FLIPPING_SPEED)),
             hitbox: HitBox(Vec2::new(20.0, 20.0)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Player,
+                &[
+                    CollisionLayer::EnemyProjectile,
+                    CollisionLayer::Pickup,
+                    CollisionLayer::Enemy,
+                ],
+            ),
         }
     }
 }
 pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_run_criteria(pause_game)
-                .with_system(player_attacking_system),
-        )
-        .add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_system(player_controls)
-                .with_system(player_walking_animation)
-                .with_system(player_flipping_animation),
-        )
-        .add_system_set(
-            SystemSet::on_update(GameState::EndStage)
-                .with_system(player_scoreboard_animation)
-                .with_system(end_stage_movement)
-                .with_system(despawner::<Shuriken>)
-                .with_system(despawner::<Effect>),
-        )
-        .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_player));
+        app.add_event::<PlayerHitEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(player_attacking_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(player_controls)
+                    .with_system(player_walking_animation)
+                    .with_system(player_flipping_animation)
+                    .with_system(player_hit_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::EndStage)
+                    .with_system(player_scoreboard_animation)
+                    .with_system(end_stage_movement)
+                    .with_system(despawner::<Shuriken>)
+                    .with_system(despawner::<Effect>),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage).with_system(despawner::<Player>),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_player));
+    }
+}
+pub fn spawn_player(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    difficulty: Res<DifficultyConfig>,
+    game_mode: Res<GameMode>,
+) {
+    if *game_mode != GameMode::Coop {
+        commands.spawn(PlayerBundle::new(
+            &game_assets,
+            difficulty.settings().ninjutsu_charges,
+            PlayerSlot::One,
+        ));
+        return;
+    }
+
+    for slot in [PlayerSlot::One, PlayerSlot::Two] {
+        commands.spawn((
+            PlayerBundle::new(&game_assets, difficulty.settings().ninjutsu_charges, slot),
+            PlayerLives(COOP_LIVES),
+        ));
     }
 }
-pub fn spawn_player(mut commands: Commands, game_assets: Res<GameAssets>) {
-    commands.spawn(PlayerBundle::new(game_assets));
+
+/// Position of the player closest to `position`, if any player is around
+pub fn nearest_player(
+    player_query: &Query<&Transform, With<Player>>,
+    position: Vec2,
+) -> Option<Vec2> {
+    player_query
+        .iter()
+        .map(|player_transform| player_transform.translation.truncate())
+        .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
 }
 
 pub fn player_controls(
     keyboard_input: Res<Input<KeyCode>>,
     time: Res<Time>,
-    mut query: Query<(&mut Player, &mut Transform, &Dimensions), With<Player>>,
+    mut query: Query<(
+        Entity,
+        &mut Player,
+        &mut Transform,
+        &Dimensions,
+        &PlayerSlot,
+    )>,
+    mut transition_events: EventWriter<PlayerTransitionEvent>,
 ) {
-    let (mut player, mut player_transform, dimensions) = query.single_mut();
-    let Bounds {
-        top,
-        right,
-        bottom,
-        left,
-    } = calculate_bounds(&player_transform, Some(dimensions.0));
-
-    if player.0 == PlayerAction::Falling || player.0 == PlayerAction::Attacking {
-        if keyboard_input.any_pressed(vec![KeyCode::Left, KeyCode::A]) && left > LEFT_WALL {
-            player_transform.translation.x -= PLAYER_AIR_SPEED * time.delta_seconds();
-        }
+    for (entity, mut player, mut player_transform, dimensions, slot) in query.iter_mut() {
+        let controls = slot.controls();
+        let Bounds {
+            top,
+            right,
+            bottom,
+            left,
+        } = calculate_bounds(&player_transform, Some(dimensions.0));
+
+        if player.0 == PlayerAction::Falling
+            || player.0 == PlayerAction::Attacking
+            || player.0 == PlayerAction::Slashing
+        {
+            if keyboard_input.any_pressed(controls.left.clone()) && left > LEFT_WALL {
+                player_transform.translation.x -= PLAYER_AIR_SPEED * time.delta_seconds();
+            }
 
-        if keyboard_input.any_pressed(vec![KeyCode::Right, KeyCode::D]) && right < RIGHT_WALL {
-            player_transform.translation.x += PLAYER_AIR_SPEED * time.delta_seconds();
-        }
+            if keyboard_input.any_pressed(controls.right.clone()) && right < RIGHT_WALL {
+                player_transform.translation.x += PLAYER_AIR_SPEED * time.delta_seconds();
+            }
 
-        if keyboard_input.pressed(KeyCode::W) && top < UPPER_BOUND {
-            player_transform.translation.y += PLAYER_AIR_SPEED * time.delta_seconds();
-        }
+            if keyboard_input.any_pressed(controls.up.clone()) && top < UPPER_BOUND {
+                player_transform.translation.y += PLAYER_AIR_SPEED * time.delta_seconds();
+            }
 
-        if keyboard_input.pressed(KeyCode::S) && bottom > LOWER_BOUND {
-            player_transform.translation.y -= PLAYER_AIR_SPEED * time.delta_seconds();
+            if keyboard_input.any_pressed(controls.down.clone()) && bottom > LOWER_BOUND {
+                player_transform.translation.y -= PLAYER_AIR_SPEED * time.delta_seconds();
+            }
         }
-    }
-
-    if keyboard_input.any_pressed(vec![KeyCode::Down, KeyCode::X])
-        && player.0 == PlayerAction::Falling
-        && player.0 != PlayerAction::Flipping
-        && player.0 != PlayerAction::WalkingLeft
-        && player.0 != PlayerAction::WalkingRight
-    {
-        player.0 = PlayerAction::Attacking;
-    }
 
-    if player.1 == LevelState::Intro {
-        if keyboard_input.just_pressed(KeyCode::C) {
-            player.0 = PlayerAction::Flipping;
+        if keyboard_input.any_pressed(controls.attack.clone()) {
+            player.transition_to(entity, PlayerAction::Attacking, &mut transition_events);
         }
 
-        if player.0 != PlayerAction::Flipping {
-            if keyboard_input.any_pressed(vec![KeyCode::Left, KeyCode::A]) {
-                player.0 = PlayerAction::WalkingLeft;
-                if left > LEFT_WALL {
+        if player.1 == LevelState::Intro {
+            if keyboard_input.any_just_pressed(controls.flip.clone()) {
+                player.transition_to(entity, PlayerAction::Flipping, &mut transition_events);
+            }
+
+            if keyboard_input.any_pressed(controls.left.clone()) {
+                player.transition_to(entity, PlayerAction::WalkingLeft, &mut transition_events);
+                if player.0 == PlayerAction::WalkingLeft && left > LEFT_WALL {
                     player_transform.translation.x -= PLAYER_WALKING_SPEED * time.delta_seconds();
                 }
             }
-            if keyboard_input.any_pressed(vec![KeyCode::Right, KeyCode::D]) {
-                player.0 = PlayerAction::WalkingRight;
-                if right < BONUS_STAGE_INTRO_RIGHT_BOUNDARY {
+
+            if keyboard_input.any_pressed(controls.right.clone()) {
+                player.transition_to(entity, PlayerAction::WalkingRight, &mut transition_events);
+                if player.0 == PlayerAction::WalkingRight
+                    && right < BONUS_STAGE_INTRO_RIGHT_BOUNDARY
+                {
                     player_transform.translation.x += PLAYER_WALKING_SPEED * time.delta_seconds();
                 }
-            } else if keyboard_input.any_just_released(vec![
-                KeyCode::Right,
-                KeyCode::D,
-                KeyCode::Left,
-                KeyCode::A,
-            ]) {
-                player.0 = PlayerAction::Idle;
+            } else if keyboard_input
+                .any_just_released(controls.left.iter().chain(controls.right.iter()).copied())
+            {
+                player.transition_to(entity, PlayerAction::Idle, &mut transition_events);
             }
         }
     }
@@ -150,96 +233,148 @@ pub fn player_attacking_system(
     time: Res<Time>,
     mut commands: Commands,
     game_assets: Res<GameAssets>,
-    mut query: Query<
-        (
-            &mut Player,
-            &mut AttackingTimer,
-            &Transform,
-            &mut TextureAtlasSprite,
-        ),
-        With<Player>,
-    >,
+    mut query: Query<(
+        Entity,
+        &mut Player,
+        &mut AttackingTimer,
+        &mut RapidFireTimer,
+        &Transform,
+        &mut TextureAtlasSprite,
+        &ActivePowerUps,
+        &PlayerSlot,
+    )>,
     mut sfx_events: EventWriter<SFXEvents>,
+    mut transition_events: EventWriter<PlayerTransitionEvent>,
     keyboard_input: Res<Input<KeyCode>>,
 ) {
-    let (mut player, mut attacking_timer, transform, mut sprite) = query.single_mut();
-
-    if player.0 == PlayerAction::Falling {
-        sprite.index = 0;
-        sprite.flip_x = false;
-    }
+    for (
+        entity,
+        mut player,
+        mut attacking_timer,
+        mut rapid_fire_timer,
+        transform,
+        mut sprite,
+        active_power_ups,
+        slot,
+    ) in query.iter_mut()
+    {
+        if player.0 != PlayerAction::Attacking {
+            continue;
+        }
 
-    if player.0 != PlayerAction::Attacking {
-        return;
-    }
+        let controls = slot.controls();
+        let is_attack_held = keyboard_input.any_pressed(controls.attack.clone());
+
+        attacking_timer.0.tick(time.delta());
+        if attacking_timer.0.just_finished() && sprite.index < 4 {
+            sprite.index = (sprite.index + 1) % 5;
+
+            if sprite.index == 4 {
+                let direction = throw_direction(&keyboard_input, &controls);
+                throw_shurikens(
+                    &mut commands,
+                    &game_assets,
+                    entity,
+                    transform.translation,
+                    direction,
+                    active_power_ups,
+                );
+                sfx_events.send(SFXEvents::ShurikenSound);
+                rapid_fire_timer.0.reset();
+            }
+        }
 
-    sprite.flip_x = false;
+        // rapid fire keeps throwing from the last frame of the animation while attack is held
+        if is_attack_held
+            && sprite.index >= 4
+            && active_power_ups.is_active(PowerUpKind::RapidFire)
+            && rapid_fire_timer.0.tick(time.delta()).just_finished()
+        {
+            let direction = throw_direction(&keyboard_input, &controls);
+            throw_shurikens(
+                &mut commands,
+                &game_assets,
+                entity,
+                transform.translation,
+                direction,
+                active_power_ups,
+            );
+            sfx_events.send(SFXEvents::ShurikenSound);
+        }
 
-    attacking_timer.0.tick(time.delta());
-    if attacking_timer.0.just_finished() && sprite.index < 4 {
-        sprite.index = (sprite.index + 1) % 5;
-        // println!("{:?}", sprite.index);
+        if !is_attack_held && sprite.index >= 4 {
+            player.transition_to(entity, PlayerAction::Falling, &mut transition_events);
+        }
+    }
+}
 
-        if sprite.index == 4 {
-            commands.spawn_empty().insert(ShurikenBundle::new(
+/// Spawns one throw of shurikens, shaped by the player's active power ups
+fn throw_shurikens(
+    commands: &mut Commands,
+    game_assets: &Res<GameAssets>,
+    owner: Entity,
+    position: Vec3,
+    direction: Vec2,
+    active_power_ups: &ActivePowerUps,
+) {
+    for direction in throw_directions(direction, active_power_ups) {
+        let mut shuriken = commands.spawn((
+            ShurikenBundle::new(
                 game_assets,
-                Vec3::new(
-                    transform.translation.x,
-                    transform.translation.y - 20.0,
-                    transform.translation.z,
-                ),
+                position + (direction.normalize() * 20.0).extend(0.0),
+                direction,
                 400.0,
-            ));
-            sfx_events.send(SFXEvents::ShurikenSound);
+            ),
+            Owner(owner),
+        ));
+
+        if active_power_ups.is_active(PowerUpKind::Piercing) {
+            shuriken.insert(Piercing);
+        }
+        if active_power_ups.is_active(PowerUpKind::Unreflectable) {
+            shuriken.insert(Unreflectable);
         }
     }
+}
 
-    if !keyboard_input.any_pressed(vec![KeyCode::Down, KeyCode::X]) && sprite.index >= 4 {
-        player.0 = PlayerAction::Falling;
+/// Direction of the next throw based on the held movement keys
+///
+/// Throws go straight down by default, diagonally down while holding a horizontal and a
+/// downward key, and horizontally while holding only a horizontal key.
+pub fn throw_direction(keyboard_input: &Input<KeyCode>, controls: &PlayerControls) -> Vec2 {
+    let mut x = 0.0;
+    if keyboard_input.any_pressed(controls.left.clone()) {
+        x -= 1.0;
     }
+    if keyboard_input.any_pressed(controls.right.clone()) {
+        x += 1.0;
+    }
+
+    let y = if x == 0.0 || keyboard_input.any_pressed(controls.aim_down.clone()) {
+        -1.0
+    } else {
+        0.0
+    };
+
+    Vec2::new(x, y)
 }
 
 pub fn player_walking_animation(
     time: Res<Time>,
-
-    mut query: Query<
-        (
-            &mut Player,
-            &mut WalkingAnimationTimer,
-            &mut TextureAtlasSprite,
-        ),
-        With<Player>,
-    >,
-    mut sfx_events: EventWriter<SFXEvents>,
+    mut query: Query<(&Player, &mut WalkingAnimationTimer, &mut TextureAtlasSprite), With<Player>>,
 ) {
-    let (player, mut walking_animation_timer, mut sprite) = query.single_mut();
-
-    if player.0 != PlayerAction::Idle
-        && player.0 != PlayerAction::WalkingLeft
-        && player.0 != PlayerAction::WalkingRight
-    {
-        return;
-    }
-
-    if player.0 == PlayerAction::Idle {
-        sprite.index = 7;
-        return;
-    }
-
-    if player.0 == PlayerAction::WalkingLeft {
-        sprite.flip_x = true;
-    } else if player.0 == PlayerAction::WalkingRight {
-        sprite.flip_x = false;
-    }
+    for (player, mut walking_animation_timer, mut sprite) in query.iter_mut() {
+        if player.0 != PlayerAction::WalkingLeft && player.0 != PlayerAction::WalkingRight {
+            continue;
+        }
 
-    if walking_animation_timer.0.tick(time.delta()).just_finished() {
-        sprite.index = sprite.index + 1;
-    }
+        if walking_animation_timer.0.tick(time.delta()).just_finished() {
+            sprite.index = sprite.index + 1;
+        }
 
-    if (player.0 == PlayerAction::WalkingLeft || player.0 == PlayerAction::WalkingRight)
-        && (sprite.index > 13 || sprite.index < 8)
-    {
-        sprite.index = 8;
+        if sprite.index > 13 || sprite.index < 8 {
+            sprite.index = 8;
+        }
     }
 }
 
@@ -248,6 +383,7 @@ pub fn player_flipping_animation(
 
     mut query: Query<
         (
+            Entity,
             &mut Player,
             &mut FlippingAnimationTimer,
             &mut TextureAtlasSprite,
@@ -257,10 +393,10 @@ pub fn player_flipping_animation(
         ),
         With<Player>,
     >,
-    mut sfx_events: EventWriter<SFXEvents>,
-    mut bonus_stage_events: ResMut<PauseEvent>,
+    mut transition_events: EventWriter<PlayerTransitionEvent>,
 ) {
     for (
+        entity,
         mut player,
         mut flipping_animation_timer,
         mut sprite,
@@ -270,11 +406,7 @@ pub fn player_flipping_animation(
     ) in query.iter_mut()
     {
         if player.0 != PlayerAction::Flipping {
-            return;
-        }
-
-        if player.0 == PlayerAction::Flipping && sprite.index < 14 {
-            sprite.index = 14;
+            continue;
         }
 
         if sprite.index == 15 {
@@ -294,19 +426,50 @@ pub fn player_flipping_animation(
         if flipping_animation_timer.tick(time.delta()).just_finished() {
             sprite.index = sprite.index + 1;
             if sprite.index > 19 {
-                player.0 = PlayerAction::Falling;
-                player.1 = LevelState::Start;
-                *bonus_stage_events = PauseEvent::Unpaused;
+                player.transition_to(entity, PlayerAction::Falling, &mut transition_events);
             }
         }
     }
 }
 
+/// Plays the hit sound, and in story mode takes a life and ends the run once none are left
+pub fn player_hit_system(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_mode: Res<GameMode>,
+    mut lives: ResMut<Lives>,
+    mut game_state: ResMut<State<GameState>>,
+    player_query: Query<&Transform, (With<Player>, Without<MarkDespawn>)>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for event in player_hit_events.iter() {
+        sfx_events.send(SFXEvents::CollisionSound);
+
+        if *game_mode != GameMode::Story || lives.0 == 0 {
+            continue;
+        }
+
+        lives.0 -= 1;
+        if lives.0 > 0 {
+            continue;
+        }
+
+        if let Ok(transform) = player_query.get(event.entity) {
+            commands.entity(event.entity).insert(MarkDespawn);
+            commands.spawn(DeathEffectBundle::new(&game_assets, transform.translation));
+        }
+
+        end_stage(&mut game_state);
+    }
+}
+
 pub fn player_scoreboard_animation(
     time: Res<Time>,
 
     mut query: Query<
         (
+            Entity,
             &mut Player,
             &mut FlippingAnimationTimer,
             &mut TextureAtlasSprite,
@@ -316,9 +479,10 @@ pub fn player_scoreboard_animation(
         ),
         With<Player>,
     >,
-    mut sfx_events: EventWriter<SFXEvents>,
+    mut transition_events: EventWriter<PlayerTransitionEvent>,
 ) {
     for (
+        entity,
         mut player,
         mut flipping_animation_timer,
         mut sprite,
@@ -327,13 +491,8 @@ pub fn player_scoreboard_animation(
         mut velocity,
     ) in query.iter_mut()
     {
-        if player.0 == PlayerAction::Landing && sprite.index < 14 {
-            sprite.index = 19;
-        }
-
         if player.0 == PlayerAction::Landed {
-            sprite.index = 14;
-            return;
+            continue;
         }
 
         if sprite.index == 15 {
@@ -343,9 +502,9 @@ pub fn player_scoreboard_animation(
                     time.delta_seconds() * (velocity.y + time.delta_seconds() * gravity.0 / 2.0);
                 velocity.y -= gravity.0 * time.delta_seconds();
             } else {
-                player.0 = PlayerAction::Landed;
+                player.transition_to(entity, PlayerAction::Landed, &mut transition_events);
             }
-            return;
+            continue;
         }
 
         if sprite.index > 15 {
@@ -356,6 +515,7 @@ pub fn player_scoreboard_animation(
 
         if flipping_animation_timer.tick(time.delta()).just_finished()
             && player.0 == PlayerAction::Landing
+            && sprite.index > 15
         {
             sprite.index = sprite.index - 1;
 
@@ -364,17 +524,20 @@ pub fn player_scoreboard_animation(
     }
 }
 
-fn end_stage_movement(
-    mut query: Query<(&mut Transform, &mut Player), With<Player>>,
+pub fn end_stage_movement(
+    mut query: Query<(Entity, &mut Transform, &mut Player, &PlayerSlot)>,
     time: Res<Time>,
+    mut transition_events: EventWriter<PlayerTransitionEvent>,
 ) {
-    for (mut transform, mut player) in query.iter_mut() {
+    for (entity, mut transform, mut player, slot) in query.iter_mut() {
         if player.0 == PlayerAction::Landing || player.0 == PlayerAction::Landed {
-            return;
+            continue;
         }
-        if transform.translation.x.round() < BONUS_STAGE_ENDING_PLAYER_STARTING_POS_X {
+
+        let target_x = BONUS_STAGE_ENDING_PLAYER_STARTING_POS_X + slot.offset_x();
+        if transform.translation.x.round() < target_x {
             transform.translation.x += PLAYER_WALKING_SPEED * time.delta_seconds();
-        } else if transform.translation.x.round() > BONUS_STAGE_ENDING_PLAYER_STARTING_POS_X {
+        } else if transform.translation.x.round() > target_x {
             transform.translation.x -= PLAYER_WALKING_SPEED * time.delta_seconds();
         }
 
@@ -385,9 +548,9 @@ fn end_stage_movement(
         }
 
         if transform.translation.y.round() == BONUS_STAGE_ENDING_PLAYER_STARTING_POS_Y
-            && transform.translation.x.round() == BONUS_STAGE_ENDING_PLAYER_STARTING_POS_X
+            && transform.translation.x.round() == target_x
         {
-            player.0 = PlayerAction::Landing;
+            player.transition_to(entity, PlayerAction::Landing, &mut transition_events);
         }
     }
 }
@@ -0,0 +1,148 @@
+use crate::prelude::*;
+
+pub struct PlayerStatePlugin;
+impl Plugin for PlayerStatePlugin {
+    fn build(&self, app: &mut App) {
+        // a single instance shared by every state, so no event is read twice when the state
+        // changes with transitions still buffered
+        app.add_event::<PlayerTransitionEvent>().add_system(
+            player_transition_hooks
+                .after(player_controls)
+                .after(player_attacking_system)
+                .after(player_flipping_animation)
+                .after(player_melee_system)
+                .after(player_scoreboard_animation)
+                .after(end_stage_movement),
+        );
+    }
+}
+
+/// Sent every time a player moves from one `PlayerAction` to another
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerTransitionEvent {
+    pub entity: Entity,
+    pub from: PlayerAction,
+    pub to: PlayerAction,
+}
+
+impl PlayerAction {
+    /// Whether the player is allowed to move from this action to `next`
+    pub fn can_transition_to(&self, next: PlayerAction) -> bool {
+        use PlayerAction::*;
+
+        matches!(
+            (self, next),
+            (Idle, WalkingLeft | WalkingRight | Flipping)
+                | (WalkingLeft, Idle | WalkingRight | Flipping)
+                | (WalkingRight, Idle | WalkingLeft | Flipping)
+                | (Flipping, Falling)
+                | (Falling, Attacking | Slashing | Landing)
+                | (Attacking, Falling | Landing)
+                | (Slashing, Falling | Landing)
+                | (Landing, Landed)
+        )
+    }
+}
+
+impl Player {
+    /// Moves the player to `next` and emits a `PlayerTransitionEvent`
+    ///
+    /// Returns `false` without changing anything if the player is already in `next`
+    /// or if the transition is not allowed.
+    pub fn transition_to(
+        &mut self,
+        entity: Entity,
+        next: PlayerAction,
+        transition_events: &mut EventWriter<PlayerTransitionEvent>,
+    ) -> bool {
+        if !self.0.can_transition_to(next) {
+            return false;
+        }
+
+        let from = self.0;
+        self.0 = next;
+        transition_events.send(PlayerTransitionEvent {
+            entity,
+            from,
+            to: next,
+        });
+
+        true
+    }
+}
+
+/// Runs the exit and enter hooks of every transition sent this frame
+///
+/// Ordered after every system that requests a transition, so the new sprite and velocity are
+/// in place before the frame is drawn.
+pub fn player_transition_hooks(
+    mut transition_events: EventReader<PlayerTransitionEvent>,
+    mut query: Query<(
+        &mut Player,
+        &mut TextureAtlasSprite,
+        &mut Velocity,
+        &mut AttackingTimer,
+        &mut SlashingAnimationTimer,
+    )>,
+    mut pause_event: ResMut<PauseEvent>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for event in transition_events.iter() {
+        let (
+            mut player,
+            mut sprite,
+            mut velocity,
+            mut attacking_timer,
+            mut slashing_animation_timer,
+        ) = match query.get_mut(event.entity) {
+            Ok(components) => components,
+            Err(_) => continue,
+        };
+
+        // exit hooks
+        if event.from == PlayerAction::Flipping && player.1 == LevelState::Intro {
+            player.1 = LevelState::Start;
+            *pause_event = PauseEvent::Unpaused;
+        }
+
+        // enter hooks
+        match event.to {
+            PlayerAction::Idle => {
+                sprite.index = 7;
+            }
+            PlayerAction::WalkingLeft => {
+                sprite.index = 8;
+                sprite.flip_x = true;
+            }
+            PlayerAction::WalkingRight => {
+                sprite.index = 8;
+                sprite.flip_x = false;
+            }
+            PlayerAction::Flipping => {
+                sprite.index = 14;
+                velocity.0 = Vec2::new(0.0, PLAYER_FLIPPING_SPEED);
+            }
+            PlayerAction::Falling => {
+                sprite.index = 0;
+                sprite.flip_x = false;
+            }
+            PlayerAction::Attacking => {
+                sprite.index = 0;
+                sprite.flip_x = false;
+                attacking_timer.0.reset();
+            }
+            PlayerAction::Slashing => {
+                sprite.index = MELEE_FIRST_FRAME;
+                sprite.flip_x = false;
+                slashing_animation_timer.0.reset();
+                sfx_events.send(SFXEvents::MeleeAttackSound);
+            }
+            PlayerAction::Landing => {
+                sprite.index = 19;
+            }
+            PlayerAction::Landed => {
+                sprite.index = 14;
+            }
+        }
+    }
+}
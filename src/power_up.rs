@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use crate::{pause_game, prelude::*};
+use rand::{seq::IteratorRandom, thread_rng, Rng};
+use ron::de::from_bytes;
+use serde::Deserialize;
+
+pub const SPREAD_ANGLE_DEG: f32 = 15.0;
+pub const RAPID_FIRE_INTERVAL_SEC: f32 = 0.15;
+/// How long a dropped power up waits to be picked up
+pub const POWER_UP_LIFETIME_SEC: f32 = 6.0;
+
+#[derive(Deserialize, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum PowerUpKind {
+    Spread,
+    Piercing,
+    RapidFire,
+    Unreflectable,
+}
+
+impl PowerUpKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PowerUpKind::Spread => "SPREAD",
+            PowerUpKind::Piercing => "PIERCE",
+            PowerUpKind::RapidFire => "RAPID",
+            PowerUpKind::Unreflectable => "STEEL",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PowerUpSettings {
+    pub duration_sec: f32,
+    pub color: (f32, f32, f32),
+}
+
+#[derive(Resource, Deserialize, Debug)]
+pub struct PowerUpConfig {
+    pub drop_chance: HashMap<EnemyType, f32>,
+    pub power_ups: HashMap<PowerUpKind, PowerUpSettings>,
+}
+
+#[derive(Component, Debug)]
+pub struct PowerUp(pub PowerUpKind);
+
+/// Time left before a dropped power up disappears
+#[derive(Component, Deref, DerefMut)]
+pub struct PowerUpLifetime(pub Timer);
+
+/// Power ups currently held by a player and the time left on each
+#[derive(Component, Default, Debug)]
+pub struct ActivePowerUps(pub HashMap<PowerUpKind, Timer>);
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.0.contains_key(&kind)
+    }
+}
+
+/// Shurikens that keep going after killing an enemy
+#[derive(Component)]
+pub struct Piercing;
+
+/// Shurikens that enemies are unable to reflect
+#[derive(Component)]
+pub struct Unreflectable;
+
+/// Time between the extra throws of a player holding attack with `RapidFire`
+#[derive(Component)]
+pub struct RapidFireTimer(pub Timer);
+
+#[derive(Bundle)]
+pub struct PowerUpBundle {
+    power_up: PowerUp,
+    lifetime: PowerUpLifetime,
+    hitbox: HitBox,
+    collision_layers: CollisionLayers,
+    sprite_bundle: SpriteBundle,
+}
+
+impl PowerUpBundle {
+    pub fn new(
+        game_assets: &Res<GameAssets>,
+        kind: PowerUpKind,
+        settings: &PowerUpSettings,
+        starting_pos: Vec3,
+    ) -> Self {
+        let (red, green, blue) = settings.color;
+
+        PowerUpBundle {
+            power_up: PowerUp(kind),
+            lifetime: PowerUpLifetime(Timer::from_seconds(POWER_UP_LIFETIME_SEC, TimerMode::Once)),
+            hitbox: HitBox(Vec2::new(16.0, 16.0)),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Pickup,
+                &[CollisionLayer::Player],
+            ),
+            sprite_bundle: SpriteBundle {
+                texture: game_assets.power_up.clone(),
+                sprite: Sprite {
+                    color: Color::rgb(red, green, blue),
+                    ..default()
+                },
+                transform: Transform::from_translation(starting_pos),
+                ..default()
+            },
+        }
+    }
+}
+
+pub struct PowerUpPlugin;
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        let power_up_config: PowerUpConfig =
+            from_bytes(include_bytes!("../data/power_ups.ron")).unwrap();
+
+        app.insert_resource(power_up_config)
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(power_up_drop_system)
+                    .with_system(power_up_collection.after(detect_collisions)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(power_up_expiry)
+                    .with_system(power_up_timers),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::EndStage).with_system(despawner::<PowerUp>),
+            );
+    }
+}
+
+pub fn power_up_drop_system(
+    mut commands: Commands,
+    mut enemy_killed_events: EventReader<EnemyKilledEvent>,
+    power_up_config: Res<PowerUpConfig>,
+    game_assets: Res<GameAssets>,
+) {
+    let mut rng = thread_rng();
+
+    for event in enemy_killed_events.iter() {
+        let drop_chance = power_up_config
+            .drop_chance
+            .get(&event.enemy_type)
+            .copied()
+            .unwrap_or_default();
+
+        if rng.gen_range(0.0..1.0) >= drop_chance {
+            continue;
+        }
+
+        if let Some((kind, settings)) = power_up_config.power_ups.iter().choose(&mut rng) {
+            commands.spawn(PowerUpBundle::new(
+                &game_assets,
+                *kind,
+                settings,
+                event.position,
+            ));
+        }
+    }
+}
+
+/// Dropped power ups stay where the enemy died until they are picked up or run out
+pub fn power_up_expiry(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut PowerUpLifetime), Without<MarkDespawn>>,
+) {
+    for (entity, mut lifetime) in query.iter_mut() {
+        if lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).insert(MarkDespawn);
+        }
+    }
+}
+
+pub fn power_up_collection(
+    mut commands: Commands,
+    mut player_touched_pickup_events: EventReader<PlayerTouchedPickupEvent>,
+    mut player_query: Query<&mut ActivePowerUps, With<Player>>,
+    power_up_query: Query<&PowerUp, Without<MarkDespawn>>,
+    power_up_config: Res<PowerUpConfig>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    for event in player_touched_pickup_events.iter() {
+        let power_up = match power_up_query.get(event.pickup) {
+            Ok(power_up) => power_up,
+            Err(_) => continue,
+        };
+
+        let mut active_power_ups = match player_query.get_mut(event.player) {
+            Ok(active_power_ups) => active_power_ups,
+            Err(_) => continue,
+        };
+
+        let duration_sec = power_up_config.power_ups[&power_up.0].duration_sec;
+        active_power_ups.0.insert(
+            power_up.0,
+            Timer::from_seconds(duration_sec, TimerMode::Once),
+        );
+
+        commands.entity(event.pickup).insert(MarkDespawn);
+        sfx_events.send(SFXEvents::PickupSound);
+    }
+}
+
+pub fn power_up_timers(time: Res<Time>, mut query: Query<&mut ActivePowerUps>) {
+    for mut active_power_ups in query.iter_mut() {
+        active_power_ups
+            .0
+            .retain(|_, timer| !timer.tick(time.delta()).finished());
+    }
+}
+
+/// Directions of every shuriken in a single throw
+pub fn throw_directions(direction: Vec2, active_power_ups: &ActivePowerUps) -> Vec<Vec2> {
+    if !active_power_ups.is_active(PowerUpKind::Spread) {
+        return vec![direction];
+    }
+
+    let spread = SPREAD_ANGLE_DEG.to_radians();
+
+    vec![
+        Vec2::from_angle(-spread).rotate(direction),
+        direction,
+        Vec2::from_angle(spread).rotate(direction),
+    ]
+}
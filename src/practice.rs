@@ -0,0 +1,338 @@
+use crate::{asset_loading::AssetHandler, pause_game, prelude::*};
+
+pub const PRACTICE_SPEEDS: [f32; 5] = [0.5, 0.75, 1.0, 1.25, 1.5];
+pub const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+/// Options picked on the practice menu
+///
+/// Practice runs never submit to the endless or time attack records.
+#[derive(Resource, Debug)]
+pub struct PracticeSettings {
+    pub difficulty: Difficulty,
+    pub wave_index: usize,
+    pub looping: bool,
+    pub invulnerable: bool,
+    /// Index into `PRACTICE_SPEEDS`
+    pub speed_index: usize,
+}
+
+impl Default for PracticeSettings {
+    fn default() -> Self {
+        PracticeSettings {
+            difficulty: Difficulty::Easy,
+            wave_index: 0,
+            looping: true,
+            invulnerable: false,
+            speed_index: 2,
+        }
+    }
+}
+
+impl PracticeSettings {
+    pub fn speed(&self) -> f32 {
+        PRACTICE_SPEEDS[self.speed_index]
+    }
+}
+
+/// Hits taken during the current practice session
+#[derive(Resource, Default, Debug)]
+pub struct PracticeHits(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PracticeRow {
+    Difficulty,
+    Wave,
+    Loop,
+    Invulnerable,
+    Speed,
+    Start,
+}
+
+pub const PRACTICE_ROWS: [PracticeRow; 6] = [
+    PracticeRow::Difficulty,
+    PracticeRow::Wave,
+    PracticeRow::Loop,
+    PracticeRow::Invulnerable,
+    PracticeRow::Speed,
+    PracticeRow::Start,
+];
+
+#[derive(Resource, Default, Debug)]
+pub struct PracticeSelection(pub usize);
+
+#[derive(Component)]
+pub struct OnPracticeMenuScreen;
+
+#[derive(Component)]
+pub struct PracticeRowText(pub PracticeRow);
+
+pub struct PracticePlugin;
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeSettings>()
+            .init_resource::<PracticeSelection>()
+            .init_resource::<PracticeHits>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::PracticeMenu).with_system(practice_menu_setup),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::PracticeMenu)
+                    .with_system(practice_menu_input)
+                    .with_system(update_practice_menu_text),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::PracticeMenu)
+                    .with_system(despawner::<OnPracticeMenuScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::InGame).with_system(start_practice_session),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(practice_wave_loop),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(practice_invulnerability)
+                    .with_system(practice_hits),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(reset_game_speed));
+    }
+}
+
+fn practice_waves<'a>(
+    level_difficulty_map: &'a LevelDifficultyMap,
+    difficulty: Difficulty,
+) -> &'a [WaveData] {
+    level_difficulty_map
+        .waves
+        .get(&difficulty)
+        .map_or(&[], |waves| waves.as_slice())
+}
+
+fn practice_row_label(
+    row: PracticeRow,
+    settings: &PracticeSettings,
+    level_difficulty_map: &LevelDifficultyMap,
+) -> String {
+    let on_off = |enabled: bool| if enabled { "ON" } else { "OFF" };
+
+    match row {
+        PracticeRow::Difficulty => format!("DIFFICULTY {:?}", settings.difficulty),
+        PracticeRow::Wave => {
+            match practice_waves(level_difficulty_map, settings.difficulty).get(settings.wave_index)
+            {
+                Some(wave) => format!(
+                    "WAVE {} {:?} x{}",
+                    settings.wave_index + 1,
+                    wave.enemy_type,
+                    wave.enemy_count
+                ),
+                None => "NO WAVES".to_string(),
+            }
+        }
+        PracticeRow::Loop => format!("LOOP {}", on_off(settings.looping)),
+        PracticeRow::Invulnerable => format!("INVULNERABLE {}", on_off(settings.invulnerable)),
+        PracticeRow::Speed => format!("SPEED {}x", settings.speed()),
+        PracticeRow::Start => "START".to_string(),
+    }
+}
+
+fn practice_menu_setup(mut commands: Commands, game_assets: Res<GameAssets>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            OnPracticeMenuScreen,
+        ))
+        .with_children(|parent| {
+            for row in PRACTICE_ROWS {
+                parent.spawn((
+                    TextBundle::from_section(
+                        String::new(),
+                        TextStyle {
+                            font: game_assets.menu_font.clone(),
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(15.0)),
+                        ..default()
+                    }),
+                    PracticeRowText(row),
+                ));
+            }
+        });
+}
+
+/// Up and down pick a row, left and right change it, Escape goes back to the main menu
+fn practice_menu_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut selection: ResMut<PracticeSelection>,
+    mut settings: ResMut<PracticeSettings>,
+    level_difficulty_map: Res<LevelDifficultyMap>,
+    mut difficulty: ResMut<DifficultyConfig>,
+    mut asset_handler: AssetHandler,
+    mut game_assets: ResMut<GameAssets>,
+    mut sfx_events: EventWriter<SFXEvents>,
+) {
+    let step: isize = if keyboard_input.just_pressed(KeyCode::Right) {
+        1
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        -1
+    } else {
+        0
+    };
+    let cycle =
+        |index: usize, len: usize| (index as isize + step).rem_euclid(len.max(1) as isize) as usize;
+
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        selection.0 = (selection.0 + 1) % PRACTICE_ROWS.len();
+        sfx_events.send(SFXEvents::MenuSFX);
+    } else if keyboard_input.just_pressed(KeyCode::Up) {
+        selection.0 = (selection.0 + PRACTICE_ROWS.len() - 1) % PRACTICE_ROWS.len();
+        sfx_events.send(SFXEvents::MenuSFX);
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        keyboard_input.reset(KeyCode::Escape);
+        sfx_events.send(SFXEvents::MenuSFX);
+        asset_handler.load(GameState::MainMenu, &mut game_assets);
+    } else if step != 0 {
+        match PRACTICE_ROWS[selection.0] {
+            PracticeRow::Difficulty => {
+                let current = DIFFICULTIES
+                    .iter()
+                    .position(|difficulty| *difficulty == settings.difficulty)
+                    .unwrap_or_default();
+                settings.difficulty = DIFFICULTIES[cycle(current, DIFFICULTIES.len())];
+                settings.wave_index = 0;
+            }
+            PracticeRow::Wave => {
+                let wave_count = practice_waves(&level_difficulty_map, settings.difficulty).len();
+                settings.wave_index = cycle(settings.wave_index, wave_count);
+            }
+            PracticeRow::Loop => settings.looping = !settings.looping,
+            PracticeRow::Invulnerable => settings.invulnerable = !settings.invulnerable,
+            PracticeRow::Speed => {
+                settings.speed_index = cycle(settings.speed_index, PRACTICE_SPEEDS.len());
+            }
+            PracticeRow::Start => (),
+        }
+        sfx_events.send(SFXEvents::MenuSFX);
+    } else if keyboard_input.any_just_pressed(vec![KeyCode::Return, KeyCode::V])
+        && PRACTICE_ROWS[selection.0] == PracticeRow::Start
+        && practice_waves(&level_difficulty_map, settings.difficulty)
+            .get(settings.wave_index)
+            .is_some()
+    {
+        // the practiced difficulty also decides things like the ninjutsu charges
+        difficulty.current = settings.difficulty;
+        sfx_events.send(SFXEvents::MenuSFX);
+        asset_handler.load(GameState::LoadWorld, &mut game_assets);
+    }
+}
+
+fn update_practice_menu_text(
+    selection: Res<PracticeSelection>,
+    settings: Res<PracticeSettings>,
+    level_difficulty_map: Res<LevelDifficultyMap>,
+    mut query: Query<(&PracticeRowText, &mut Text)>,
+) {
+    for (row_text, mut text) in query.iter_mut() {
+        let section = &mut text.sections[0];
+        section.value = practice_row_label(row_text.0, &settings, &level_difficulty_map);
+        section.style.color = if PRACTICE_ROWS[selection.0] == row_text.0 {
+            Color::RED
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+fn practice_wave(settings: &PracticeSettings, level_difficulty_map: &LevelDifficultyMap) -> Wave {
+    let wave_data = &practice_waves(level_difficulty_map, settings.difficulty)[settings.wave_index];
+
+    Wave {
+        // a looped wave only restarts once the previous one is gone
+        wait_for_clear: true,
+        ..Wave::from(wave_data)
+    }
+}
+
+pub fn start_practice_session(
+    game_mode: Res<GameMode>,
+    settings: Res<PracticeSettings>,
+    level_difficulty_map: Res<LevelDifficultyMap>,
+    mut time: ResMut<Time>,
+    mut level: ResMut<Level>,
+    mut score: ResMut<Score>,
+    mut practice_hits: ResMut<PracticeHits>,
+) {
+    if *game_mode != GameMode::Practice {
+        return;
+    }
+
+    *level = Level(vec![practice_wave(&settings, &level_difficulty_map)], 0);
+    score.0 = 0;
+    practice_hits.0 = 0;
+    time.set_relative_speed(settings.speed());
+}
+
+/// Restarts the practiced wave once it is cleared, or ends the session when not looping
+pub fn practice_wave_loop(
+    game_mode: Res<GameMode>,
+    settings: Res<PracticeSettings>,
+    level_difficulty_map: Res<LevelDifficultyMap>,
+    mut level: ResMut<Level>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if *game_mode != GameMode::Practice || level.1 < level.0.len() {
+        return;
+    }
+
+    if settings.looping {
+        *level = Level(vec![practice_wave(&settings, &level_difficulty_map)], 0);
+    } else {
+        end_stage(&mut game_state);
+    }
+}
+
+pub fn practice_invulnerability(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    settings: Res<PracticeSettings>,
+    query: Query<Entity, Added<Player>>,
+) {
+    if *game_mode != GameMode::Practice || !settings.invulnerable {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).insert(Invulnerable);
+    }
+}
+
+pub fn practice_hits(
+    game_mode: Res<GameMode>,
+    mut practice_hits: ResMut<PracticeHits>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+) {
+    let hits = player_hit_events.iter().count() as u32;
+
+    if *game_mode == GameMode::Practice && hits > 0 {
+        practice_hits.0 += hits;
+    }
+}
+
+pub fn reset_game_speed(mut time: ResMut<Time>) {
+    time.set_relative_speed(1.0);
+}
@@ -3,9 +3,28 @@ use crate::prelude::*;
 #[derive(Resource, Clone)]
 pub struct EnemyCount(pub u32);
 
+#[derive(Resource, Default, Debug)]
+pub struct Score(pub u32);
+
 #[derive(Default, Resource, PartialEq, Eq, Debug)]
 pub enum PauseEvent {
     Paused,
     #[default]
     Unpaused,
 }
+
+/// How a run started from the main menu plays out
+#[derive(Resource, Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GameMode {
+    #[default]
+    Story,
+    Endless,
+    TimeAttack,
+    Practice,
+    /// Two players sharing the bonus stage
+    Coop,
+}
+
+/// Hits the player can take before the run is over, only counted in modes that can be lost
+#[derive(Resource, Default, Debug)]
+pub struct Lives(pub u32);
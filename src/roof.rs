@@ -59,7 +59,8 @@ impl Plugin for RoofPlugin {
                 
                 .with_run_criteria(pause_game)
                 .with_system(roof_animator),
-        );
+        )
+        .add_system_set(SystemSet::on_exit(GameState::EndStage).with_system(despawner::<Roof>));
     }
 }
 
@@ -0,0 +1,37 @@
+use std::{fs, path::Path};
+
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const SAVE_DIR: &str = "saves";
+
+/// Reads `file_name` from the save directory, falling back to the default when it is missing or unreadable
+pub fn load_save<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let path = Path::new(SAVE_DIR).join(file_name);
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => ron::from_str(&contents).unwrap_or_else(|error| {
+            println!("Ignoring corrupt save {:?}: {}", path, error);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Writes `value` to `file_name` in the save directory
+///
+/// Failing to save only costs the player their records so errors are logged and not propagated.
+pub fn write_save<T: Serialize>(file_name: &str, value: &T) {
+    let path = Path::new(SAVE_DIR).join(file_name);
+
+    let result = fs::create_dir_all(SAVE_DIR)
+        .map_err(|error| error.to_string())
+        .and_then(|_| {
+            to_string_pretty(value, PrettyConfig::default()).map_err(|error| error.to_string())
+        })
+        .and_then(|contents| fs::write(&path, contents).map_err(|error| error.to_string()));
+
+    if let Err(error) = result {
+        println!("Could not write save {:?}: {}", path, error);
+    }
+}
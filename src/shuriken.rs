@@ -1,3 +1,5 @@
+use rand::Rng;
+
 use crate::{assets::GameAssets, pause_game, prelude::*};
 
 #[derive(Component)]
@@ -6,6 +8,18 @@ pub struct ShurikenSpeed(pub f32);
 #[derive(Component)]
 pub struct Reflected(pub f32);
 
+/// Spin added on top of the direction a shuriken was thrown in
+#[derive(Component)]
+pub struct ShurikenSpin {
+    pub throw_angle: f32,
+    pub spin: f32,
+}
+
+pub const SHURIKEN_HITBOX: Vec2 = Vec2::new(8.0, 8.0);
+pub const SHURIKEN_DAMAGE: u32 = 1;
+/// Degrees per second
+pub const SHURIKEN_SPIN_SPEED: f32 = 1060.0;
+
 #[derive(Bundle)]
 pub struct ShurikenBundle {
     shuriken: Shuriken,
@@ -14,20 +28,49 @@ pub struct ShurikenBundle {
     hitbox: HitBox,
     sprite_bundle: SpriteBundle,
     speed: ShurikenSpeed,
+    spin: ShurikenSpin,
+    previous_position: PreviousPosition,
+    collision_layers: CollisionLayers,
 }
 
 impl ShurikenBundle {
-    pub fn new(game_assets: Res<GameAssets>, starting_pos: Vec3, shuriken_speed: f32) -> Self {
+    /// Creates a new shuriken
+    ///
+    /// # Arguments
+    ///
+    /// * `starting_pos` - Position the shuriken is thrown from
+    /// * `direction` - Direction of the throw, does not need to be normalized
+    /// * `shuriken_speed` - Speed of the shuriken along `direction`
+    ///
+    pub fn new(
+        game_assets: &Res<GameAssets>,
+        starting_pos: Vec3,
+        direction: Vec2,
+        shuriken_speed: f32,
+    ) -> Self {
+        let direction = direction.try_normalize().unwrap_or(Vec2::NEG_Y);
+        let angle = direction.y.atan2(direction.x);
+
         ShurikenBundle {
             shuriken: Shuriken,
             animation_timer: AnimationTimer(Timer::from_seconds(2.0, TimerMode::Repeating)),
-            velocity: Velocity(Vec2::new(0.0, -400.0)),
-            hitbox: HitBox(Vec2::new(8.0, 8.0)),
+            velocity: Velocity(direction * shuriken_speed),
+            hitbox: HitBox::oriented(SHURIKEN_HITBOX, direction),
             speed: ShurikenSpeed(shuriken_speed),
+            spin: ShurikenSpin {
+                throw_angle: angle,
+                spin: 0.0,
+            },
+            previous_position: PreviousPosition(starting_pos.truncate()),
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::PlayerProjectile,
+                &[CollisionLayer::Enemy],
+            ),
             sprite_bundle: SpriteBundle {
                 texture: game_assets.shuriken.clone(),
                 transform: Transform {
                     translation: starting_pos,
+                    rotation: Quat::from_rotation_z(angle),
                     ..Default::default()
                 },
                 ..default()
@@ -36,6 +79,19 @@ impl ShurikenBundle {
     }
 }
 
+impl HitBox {
+    /// Axis aligned box that encloses a `size` box rotated to face `direction`
+    pub fn oriented(size: Vec2, direction: Vec2) -> Self {
+        let direction = direction.try_normalize().unwrap_or(Vec2::X);
+        let (cos, sin) = (direction.x.abs(), direction.y.abs());
+
+        HitBox(Vec2::new(
+            size.x * cos + size.y * sin,
+            size.x * sin + size.y * cos,
+        ))
+    }
+}
+
 pub struct ShurikenPlugin;
 impl Plugin for ShurikenPlugin {
     fn build(&self, app: &mut App) {
@@ -45,6 +101,11 @@ impl Plugin for ShurikenPlugin {
                 .with_system(shuriken_movement)
                 .with_system(shuriken_animator)
                 .with_system(reflect_projectile),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_system(shuriken_hit_enemy.after(detect_collisions))
+                .with_system(reflected_shuriken_hit_player.after(detect_collisions)),
         );
     }
 }
@@ -52,9 +113,20 @@ impl Plugin for ShurikenPlugin {
 pub fn shuriken_movement(
     time: Res<Time>,
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Transform, &Velocity, &mut ShurikenSpeed), With<Shuriken>>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &Velocity,
+            &mut ShurikenSpeed,
+            &mut PreviousPosition,
+        ),
+        With<Shuriken>,
+    >,
 ) {
-    for (entity, mut transform, velocity, mut speed) in query.iter_mut() {
+    for (entity, mut transform, velocity, mut speed, mut previous_position) in query.iter_mut() {
+        previous_position.0 = transform.translation.truncate();
+
         let Bounds { right, left, .. } = calculate_bounds(&transform, None);
 
         let is_touching_left_bound = left < LEFT_WALL;
@@ -63,6 +135,9 @@ pub fn shuriken_movement(
         if is_touching_left_bound || is_touching_right_bound {
             speed.0 = 0.0;
             transform.translation.y += FALLING_SPEED * time.delta().as_secs_f32();
+            if transform.translation.y > (WORLD_HEIGHT / 2.0) + 100.0 {
+                commands.entity(entity).insert(MarkDespawn);
+            }
         } else {
             transform.translation.y += velocity.y * time.delta().as_secs_f32();
             transform.translation.x += velocity.x * time.delta().as_secs_f32();
@@ -75,12 +150,16 @@ pub fn shuriken_movement(
 
 pub fn shuriken_animator(
     time: Res<Time>,
-    mut query: Query<(&mut Transform, &ShurikenSpeed), With<Shuriken>>,
+    mut query: Query<(&mut Transform, &ShurikenSpeed, &mut ShurikenSpin), With<Shuriken>>,
 ) {
-    for (mut transform, speed) in query.iter_mut() {
+    for (mut transform, speed, mut spin) in query.iter_mut() {
         if speed.0 > 0.0 {
-            transform.rotate_z(f32::to_radians(1060.0) * time.delta_seconds());
+            spin.spin = (spin.spin + SHURIKEN_SPIN_SPEED.to_radians() * time.delta_seconds())
+                % std::f32::consts::TAU;
         }
+
+        // the spin is measured from the throw direction so angled throws keep their facing
+        transform.rotation = Quat::from_rotation_z(spin.throw_angle + spin.spin);
     }
 }
 
@@ -95,3 +174,137 @@ pub fn reflect_projectile(
         shuriken_velocity.y = speed.0 * reflected.0.sin();
     }
 }
+
+/// Shurikens damage the enemies they reach unless the enemy blocks or reflects them
+pub fn shuriken_hit_enemy(
+    mut commands: Commands,
+    mut projectile_hit_enemy_events: EventReader<ProjectileHitEnemyEvent>,
+    player_query: Query<&Transform, With<Player>>,
+    shuriken_query: Query<
+        (
+            &Transform,
+            &Velocity,
+            Option<&Piercing>,
+            Option<&Unreflectable>,
+            Option<&Owner>,
+        ),
+        (With<Shuriken>, Without<MarkDespawn>, Without<Reflected>),
+    >,
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &mut Enemy,
+            &ReflectChance,
+            &EnemyType,
+            &mut Health,
+            Option<&Shield>,
+        ),
+        Without<MarkDespawn>,
+    >,
+    mut sfx_events: EventWriter<SFXEvents>,
+    mut enemy_hit_events: EventWriter<EnemyHitEvent>,
+    mut enemy_killed_events: EventWriter<EnemyKilledEvent>,
+) {
+    // shurikens stopped earlier this frame ignore anything else along their path
+    let mut spent_shurikens = Vec::new();
+
+    for event in projectile_hit_enemy_events.iter() {
+        if spent_shurikens.contains(&event.projectile) {
+            continue;
+        }
+
+        let (shuriken_transform, shuriken_velocity, piercing, unreflectable, owner) =
+            match shuriken_query.get(event.projectile) {
+                Ok(shuriken) => shuriken,
+                Err(_) => continue,
+            };
+
+        let (enemy_transform, mut enemy, reflect_chance, enemy_type, mut health, shield) =
+            match enemy_query.get_mut(event.enemy) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+
+        if enemy.0 == EnemyState::Dead {
+            continue;
+        }
+
+        // reflections fly back at whoever threw the shuriken
+        let shuriken_position = shuriken_transform.translation.truncate();
+        let angle = match owner
+            .and_then(|owner| player_query.get(owner.0).ok())
+            .map(|player_transform| player_transform.translation.truncate())
+            .or_else(|| nearest_player(&player_query, shuriken_position))
+        {
+            Some(target) => f32::atan2(
+                target.y - shuriken_position.y,
+                target.x - shuriken_position.x,
+            ),
+            None => std::f32::consts::FRAC_PI_2,
+        };
+
+        if shield.map_or(false, |shield| shield.covers(shuriken_velocity.0)) {
+            if unreflectable.is_some() {
+                commands.entity(event.projectile).insert(MarkDespawn);
+            } else {
+                commands.entity(event.projectile).insert(Reflected(angle));
+            }
+            commands
+                .entity(event.enemy)
+                .insert(BlockingTimer(Timer::from_seconds(0.2, TimerMode::Once)));
+            sfx_events.send(SFXEvents::ReflectionSound);
+            spent_shurikens.push(event.projectile);
+            continue;
+        }
+
+        // only roll for a reflection once the shuriken actually connects
+        let reflects = (enemy.0 == EnemyState::Airborne
+            && rand::thread_rng().gen_range(0.0..=1.0) < reflect_chance.0)
+            || enemy.0 == EnemyState::WallHanging;
+
+        if reflects && unreflectable.is_none() {
+            commands.entity(event.projectile).insert(Reflected(angle));
+            sfx_events.send(SFXEvents::ReflectionSound);
+            spent_shurikens.push(event.projectile);
+            continue;
+        }
+
+        if !health.damage(SHURIKEN_DAMAGE) {
+            commands.entity(event.projectile).insert(MarkDespawn);
+            enemy_hit_events.send(EnemyHitEvent {
+                entity: event.enemy,
+                knockback: shuriken_velocity.0,
+            });
+            spent_shurikens.push(event.projectile);
+            continue;
+        }
+
+        sfx_events.send(SFXEvents::CollisionSound);
+
+        enemy.0 = EnemyState::Dead;
+        enemy_killed_events.send(EnemyKilledEvent {
+            entity: event.enemy,
+            enemy_type: *enemy_type,
+            position: enemy_transform.translation,
+            killer: owner.map(|owner| owner.0),
+        });
+
+        if piercing.is_none() {
+            commands.entity(event.projectile).insert(MarkDespawn);
+            spent_shurikens.push(event.projectile);
+        }
+    }
+}
+
+/// Reflected shurikens are caught by the player they fly into
+pub fn reflected_shuriken_hit_player(
+    mut commands: Commands,
+    mut projectile_hit_player_events: EventReader<ProjectileHitPlayerEvent>,
+    shuriken_query: Query<(), (With<Shuriken>, With<Reflected>, Without<MarkDespawn>)>,
+) {
+    for event in projectile_hit_player_events.iter() {
+        if shuriken_query.contains(event.projectile) {
+            commands.entity(event.projectile).insert(MarkDespawn);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Side of a grid cell, a bit larger than the biggest regular enemy
+pub const GRID_CELL_SIZE: f32 = 64.0;
+/// Extra room around every query so things that moved after the grid was built are still found
+pub const GRID_QUERY_MARGIN: f32 = 16.0;
+
+/// Uniform grid broad phase shared by the collision checks
+///
+/// Every collider is bucketed into the cells its box touches, so a query only has to look at the
+/// entities around it instead of every entity in the stage.
+#[derive(Resource, Debug)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        SpatialGrid::new(GRID_CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    /// Empties every cell while keeping their allocations for the next rebuild
+    pub fn clear(&mut self) {
+        for entities in self.cells.values_mut() {
+            entities.clear();
+        }
+    }
+
+    fn cell_range(&self, center: Vec2, size: Vec2) -> (IVec2, IVec2) {
+        let half_size = size.abs() / 2.0;
+        let min = ((center - half_size) / self.cell_size).floor();
+        let max = ((center + half_size) / self.cell_size).floor();
+
+        (min.as_ivec2(), max.as_ivec2())
+    }
+
+    /// Adds a collider given by the center and size of its box
+    pub fn insert(&mut self, entity: Entity, center: Vec2, size: Vec2) {
+        let (min, max) = self.cell_range(center, size);
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry((x, y)).or_default().push(entity);
+            }
+        }
+    }
+
+    /// Every collider sharing a cell with the given box, each listed once
+    ///
+    /// This is only the broad phase, callers still have to check the boxes actually overlap.
+    pub fn query(&self, center: Vec2, size: Vec2) -> Vec<Entity> {
+        let (min, max) = self.cell_range(center, size + GRID_QUERY_MARGIN * 2.0);
+
+        let mut entities = Vec::new();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    entities.extend_from_slice(cell);
+                }
+            }
+        }
+
+        entities.sort_unstable();
+        entities.dedup();
+        entities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_finds_a_box_spanning_cells_from_either_side() {
+        let mut grid = SpatialGrid::new(64.0);
+        let entity = Entity::from_raw(1);
+        grid.insert(entity, Vec2::new(64.0, 0.0), Vec2::new(40.0, 40.0));
+
+        assert_eq!(grid.query(Vec2::new(20.0, 10.0), Vec2::ONE), vec![entity]);
+        assert_eq!(grid.query(Vec2::new(100.0, 10.0), Vec2::ONE), vec![entity]);
+        // listed once even though the query covers both of its cells
+        assert_eq!(
+            grid.query(Vec2::new(64.0, 0.0), Vec2::new(128.0, 8.0)),
+            vec![entity]
+        );
+        assert!(grid.query(Vec2::new(300.0, 300.0), Vec2::ONE).is_empty());
+    }
+
+    #[test]
+    fn query_rounds_negative_coordinates_down() {
+        let mut grid = SpatialGrid::new(64.0);
+        let entity = Entity::from_raw(1);
+        grid.insert(entity, Vec2::new(-10.0, -10.0), Vec2::new(8.0, 8.0));
+
+        assert_eq!(
+            grid.query(Vec2::new(-40.0, -40.0), Vec2::new(4.0, 4.0)),
+            vec![entity]
+        );
+        // truncating towards zero would put both boxes in cell (0, 0)
+        assert!(grid
+            .query(Vec2::new(40.0, 40.0), Vec2::new(4.0, 4.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn clear_empties_every_cell() {
+        let mut grid = SpatialGrid::new(64.0);
+        grid.insert(Entity::from_raw(1), Vec2::ZERO, Vec2::new(200.0, 200.0));
+        grid.clear();
+
+        assert!(grid.query(Vec2::ZERO, Vec2::new(200.0, 200.0)).is_empty());
+    }
+}
@@ -0,0 +1,456 @@
+//! Headless runs of the gameplay plugins with zero, one and two players, making sure no system
+//! assumes exactly one player exists and that attacks still reach their targets
+
+use std::time::Duration;
+
+use crate::{pause_game, prelude::*};
+use bevy::{
+    ecs::system::{System, SystemState},
+    input::{keyboard::KeyboardInput, ButtonState, InputPlugin},
+    time::TimeUpdateStrategy,
+    utils::Instant,
+};
+
+const FRAMES: usize = 30;
+/// Every update advances the clock by the same step so timers and movement are repeatable
+const FRAME_TIME: Duration = Duration::from_millis(16);
+const ENEMY_TYPES: [EnemyType; 5] = [
+    EnemyType::Pawn,
+    EnemyType::ShieldNinja,
+    EnemyType::Thrower,
+    EnemyType::Diver,
+    EnemyType::Armored,
+];
+
+/// The gameplay plugins on top of `MinimalPlugins`, with the resources and events the rest of
+/// the game would otherwise provide
+fn headless_app(game_state: GameState) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(InputPlugin)
+        .add_asset::<TextureAtlas>()
+        .init_resource::<GameAssets>()
+        .init_resource::<PauseEvent>()
+        .init_resource::<Score>()
+        .init_resource::<GameMode>()
+        .init_resource::<Lives>()
+        .init_resource::<StageObjective>()
+        .insert_resource(GameScriptState::new(vec![StageEntry {
+            name: "Stage 1-1".to_string(),
+            script: GameScript::Platforming {
+                level: "1-1".to_string(),
+            },
+        }]))
+        .add_event::<SFXEvents>()
+        .add_event::<BGMEvents>()
+        .add_plugin(DifficultyPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(PlayerStatePlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(ShurikenPlugin)
+        .add_plugin(EnemyPlugin)
+        .add_plugin(EnemyProjectilePlugin)
+        .add_plugin(MeleePlugin)
+        .add_plugin(NinjutsuPlugin)
+        .add_plugin(BossPlugin)
+        .add_plugin(WavePlugin)
+        .add_plugin(CoopPlugin)
+        .add_plugin(PlatformingPlugin)
+        .add_plugin(DogPlugin)
+        .add_plugin(TiledPlugin)
+        .add_state(game_state);
+    app.world.spawn((Camera::default(), Transform::default()));
+
+    app
+}
+
+/// Runs `frames` updates, each `FRAME_TIME` after the one before
+fn run_frames(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        let last_update = app
+            .world
+            .resource::<Time>()
+            .last_update()
+            .unwrap_or_else(Instant::now);
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(last_update + FRAME_TIME));
+        app.update();
+    }
+}
+
+/// Despawns or spawns `T` entities until there are exactly `players` of them
+fn set_player_count<T: Component>(
+    app: &mut App,
+    players: usize,
+    spawn: impl Fn(&mut Commands, &Res<GameAssets>),
+) {
+    let mut system_state: SystemState<(Commands, Res<GameAssets>, Query<Entity, With<T>>)> =
+        SystemState::new(&mut app.world);
+    {
+        let (mut commands, game_assets, query) = system_state.get_mut(&mut app.world);
+        let existing = query.iter().collect::<Vec<Entity>>();
+
+        for entity in existing.iter().skip(players) {
+            commands.entity(*entity).despawn();
+        }
+        for _ in existing.len()..players {
+            spawn(&mut commands, &game_assets);
+        }
+    }
+    system_state.apply(&mut app.world);
+
+    assert_eq!(count::<T>(app), players);
+}
+
+/// An in game stage with `players` players past the intro, so the paused systems run as well
+fn in_game_with_players(players: usize) -> App {
+    let mut app = headless_app(GameState::InGame);
+    app.update();
+    set_player_count::<Player>(&mut app, players, |commands, game_assets| {
+        commands.spawn(PlayerBundle::new(game_assets, 0, PlayerSlot::Two));
+    });
+
+    for mut player in app.world.query::<&mut Player>().iter_mut(&mut app.world) {
+        player.1 = LevelState::Start;
+    }
+
+    app
+}
+
+/// Spawns one enemy of every archetype in a row starting at `position`, holding them still
+fn spawn_enemies(app: &mut App, position: Vec2) -> Vec<(EnemyType, Entity)> {
+    let mut system_state: SystemState<(Commands, Res<GameAssets>, Res<EnemyConfig>)> =
+        SystemState::new(&mut app.world);
+    let enemies = {
+        let (mut commands, game_assets, enemy_config) = system_state.get_mut(&mut app.world);
+
+        ENEMY_TYPES
+            .iter()
+            .map(|enemy_type| {
+                let enemy = spawn_enemy(
+                    &mut commands,
+                    *enemy_type,
+                    &game_assets,
+                    StartingWall::Left,
+                    position.y,
+                    &EnemyOverrides::default(),
+                    &enemy_config,
+                )
+                .unwrap();
+                (*enemy_type, enemy)
+            })
+            .collect::<Vec<(EnemyType, Entity)>>()
+    };
+    system_state.apply(&mut app.world);
+
+    for (index, (_, enemy)) in enemies.iter().enumerate() {
+        let translation = (position + Vec2::new(index as f32 * 60.0, 0.0)).extend(1.0);
+        app.world.entity_mut(*enemy).insert((
+            Transform::from_translation(translation),
+            Velocity(Vec2::ZERO),
+            Gravity(0.0),
+            ReflectChance(0.0),
+        ));
+    }
+
+    enemies
+}
+
+fn throw_shuriken(app: &mut App, position: Vec3, direction: Vec2) -> Entity {
+    let mut system_state: SystemState<(Commands, Res<GameAssets>)> =
+        SystemState::new(&mut app.world);
+    let shuriken = {
+        let (mut commands, game_assets) = system_state.get_mut(&mut app.world);
+        commands
+            .spawn(ShurikenBundle::new(
+                &game_assets,
+                position,
+                direction,
+                PLATFORM_SHURIKEN_SPEED,
+            ))
+            .id()
+    };
+    system_state.apply(&mut app.world);
+
+    shuriken
+}
+
+fn translation(app: &App, entity: Entity) -> Vec3 {
+    app.world.get::<Transform>(entity).unwrap().translation
+}
+
+fn is_dead(app: &App, enemy: Entity) -> bool {
+    app.world
+        .get::<Enemy>(enemy)
+        .map_or(true, |enemy| enemy.0 == EnemyState::Dead)
+}
+
+fn players(app: &mut App) -> Vec<Entity> {
+    let mut players = app
+        .world
+        .query_filtered::<(Entity, &PlayerSlot), With<Player>>()
+        .iter(&app.world)
+        .map(|(entity, slot)| (*slot, entity))
+        .collect::<Vec<(PlayerSlot, Entity)>>();
+    players.sort_by_key(|(slot, _)| *slot);
+
+    players.into_iter().map(|(_, entity)| entity).collect()
+}
+
+fn count<T: Component>(app: &mut App) -> usize {
+    app.world
+        .query_filtered::<(), With<T>>()
+        .iter(&app.world)
+        .count()
+}
+
+/// Presses `keys` through input events so they also count as just pressed on the next frame
+fn press(app: &mut App, keys: &[KeyCode]) {
+    for key in keys {
+        app.world.send_event(KeyboardInput {
+            scan_code: 0,
+            key_code: Some(*key),
+            state: ButtonState::Pressed,
+        });
+    }
+}
+
+/// Runs a stage with every player past the intro while shurikens hit one enemy of each archetype
+fn run_in_game(players: usize) {
+    let mut app = in_game_with_players(players);
+
+    // an enemy projectile right where the players spawn
+    app.world.spawn((
+        Transform::from_translation(BONUS_STAGE_SPAWN_POS.extend(2.0)),
+        HitBox(Vec2::new(20.0, 20.0)),
+        CollisionLayers::new(CollisionLayer::EnemyProjectile, &[CollisionLayer::Player]),
+    ));
+
+    // thrown upwards so the shield does not cover them
+    let enemies = spawn_enemies(&mut app, Vec2::new(-120.0, 120.0));
+    for (_, enemy) in enemies.iter() {
+        let position = translation(&app, *enemy);
+        throw_shuriken(&mut app, position, Vec2::Y);
+    }
+
+    press(&mut app, &[KeyCode::Left, KeyCode::X, KeyCode::J]);
+    run_frames(&mut app, FRAMES);
+    press(&mut app, &[KeyCode::Down, KeyCode::C]);
+    run_frames(&mut app, FRAMES);
+
+    assert_eq!(count::<Player>(&mut app), players);
+    for (enemy_type, enemy) in enemies.iter() {
+        match enemy_type {
+            EnemyType::Armored => {
+                let health = app.world.get::<Health>(*enemy).unwrap();
+                assert!(health.0 > 0 && health.0 < 3, "armor took {:?}", health.0);
+            }
+            _ => assert!(is_dead(&app, *enemy), "{:?} survived", enemy_type),
+        }
+    }
+    assert!(app.world.resource::<Score>().0 >= 4 * ENEMY_KILL_SCORE);
+}
+
+/// Runs a platforming stage with Yamato following along, returning where the camera started
+fn run_platforming(players: usize) -> (App, f32) {
+    let mut app = headless_app(GameState::Platforming);
+    // the second frame lets Yamato join the player the stage spawned
+    app.update();
+    app.update();
+    set_player_count::<PlatformPlayer>(&mut app, players, |commands, game_assets| {
+        commands.spawn(PlatformPlayerBundle::new(
+            game_assets,
+            Vec2::new(PLATFORM_CAMERA_HALF_WIDTH * 3.0, 0.0),
+            PlayerSlot::Two,
+        ));
+    });
+    let camera_start = camera_x(&mut app);
+
+    press(&mut app, &[KeyCode::Right, KeyCode::X, KeyCode::S]);
+    run_frames(&mut app, FRAMES);
+
+    assert!(count::<Dog>(&mut app) >= 1);
+    (app, camera_start)
+}
+
+fn camera_x(app: &mut App) -> f32 {
+    app.world
+        .query_filtered::<&Transform, With<Camera>>()
+        .single(&app.world)
+        .translation
+        .x
+}
+
+#[test]
+fn in_game_without_players() {
+    run_in_game(0);
+}
+
+#[test]
+fn in_game_with_one_player() {
+    run_in_game(1);
+}
+
+#[test]
+fn in_game_with_two_players() {
+    run_in_game(2);
+}
+
+#[test]
+fn shields_send_shurikens_back_at_the_player() {
+    let mut app = in_game_with_players(1);
+    let player = players(&mut app)[0];
+    let above_player = translation(&app, player).truncate() + Vec2::new(0.0, 150.0);
+    let enemies = spawn_enemies(&mut app, above_player - Vec2::new(60.0, 0.0));
+    let shield_ninja = enemies[1].1;
+
+    let shield_position = translation(&app, shield_ninja);
+    let shuriken = throw_shuriken(&mut app, shield_position, Vec2::NEG_Y);
+    run_frames(&mut app, 1);
+
+    assert!(app.world.get::<Reflected>(shuriken).is_some());
+    assert!(!is_dead(&app, shield_ninja));
+
+    run_frames(&mut app, FRAMES);
+
+    assert!(app.world.get::<MarkDespawn>(shuriken).is_some());
+}
+
+#[test]
+fn throwers_aim_at_the_nearest_player() {
+    let mut app = in_game_with_players(2);
+    let players = players(&mut app);
+    let enemies = spawn_enemies(&mut app, Vec2::new(100.0, 100.0));
+    let thrower = enemies[2].1;
+
+    app.world
+        .entity_mut(players[0])
+        .insert(Transform::from_xyz(-200.0, -28.0, 2.0));
+    app.world
+        .entity_mut(players[1])
+        .insert(Transform::from_xyz(200.0, -28.0, 2.0));
+    app.world.get_mut::<Enemy>(thrower).unwrap().0 = EnemyState::WallHanging;
+    {
+        let mut projectile_thrower = app.world.get_mut::<ProjectileThrower>(thrower).unwrap();
+        projectile_thrower.accuracy = 1.0;
+        let almost_due = projectile_thrower.fire_timer.duration() - FRAME_TIME / 2;
+        projectile_thrower.fire_timer.set_elapsed(almost_due);
+    }
+    run_frames(&mut app, 1);
+
+    let thrower_position = translation(&app, thrower).truncate();
+    let aim = translation(&app, players[1]).truncate() - thrower_position;
+    let velocity = app
+        .world
+        .query_filtered::<&Velocity, With<EnemyProjectile>>()
+        .single(&app.world)
+        .0;
+
+    assert!(velocity.angle_between(aim).abs() < 0.05);
+}
+
+#[test]
+fn divers_curve_towards_the_nearest_player() {
+    let mut app = in_game_with_players(2);
+    let players = players(&mut app);
+    let enemies = spawn_enemies(&mut app, Vec2::new(100.0, 0.0));
+    let diver = enemies[3].1;
+
+    app.world
+        .entity_mut(players[0])
+        .insert(Transform::from_xyz(-300.0, 0.0, 2.0));
+    app.world
+        .entity_mut(players[1])
+        .insert(Transform::from_xyz(400.0, 0.0, 2.0));
+    app.world.entity_mut(diver).insert((
+        Transform::from_xyz(200.0, 0.0, 1.0),
+        Velocity(Vec2::new(0.0, 300.0)),
+    ));
+    app.world
+        .get_mut::<Diver>(diver)
+        .unwrap()
+        .dive_timer
+        .reset();
+    run_frames(&mut app, 10);
+
+    assert!(app.world.get::<Velocity>(diver).unwrap().x > 0.0);
+}
+
+#[test]
+fn melee_slashes_hit_enemies_next_to_the_player() {
+    let mut app = in_game_with_players(1);
+    let player = players(&mut app)[0];
+    let player_position = translation(&app, player).truncate();
+    let enemies = spawn_enemies(&mut app, player_position);
+    // players only slash mid-air
+    app.world.get_mut::<Player>(player).unwrap().0 = PlayerAction::Falling;
+    let pawn = enemies[0].1;
+
+    press(&mut app, &PlayerSlot::One.controls().melee);
+    run_frames(&mut app, 2);
+
+    assert!(count::<MeleeSlash>(&mut app) >= 1);
+    assert!(is_dead(&app, pawn));
+}
+
+#[test]
+fn ninjutsu_kills_every_enemy_on_screen() {
+    let mut app = in_game_with_players(1);
+    let player = players(&mut app)[0];
+    app.world.entity_mut(player).insert(NinjutsuCharges(1));
+    let enemies = spawn_enemies(&mut app, Vec2::new(-120.0, 0.0));
+
+    press(&mut app, &PlayerSlot::One.controls().ninjutsu);
+    run_frames(&mut app, 1);
+
+    assert_eq!(app.world.get::<NinjutsuCharges>(player).unwrap().0, 0);
+    assert_eq!(count::<NinjutsuFlash>(&mut app), 1);
+    for (enemy_type, enemy) in enemies.iter() {
+        assert!(is_dead(&app, *enemy), "{:?} survived", enemy_type);
+    }
+}
+
+#[test]
+fn platforming_without_players_leaves_the_camera_alone() {
+    let (mut app, camera_start) = run_platforming(0);
+
+    assert_eq!(camera_x(&mut app), camera_start);
+}
+
+#[test]
+fn platforming_with_one_player() {
+    let (mut app, _) = run_platforming(1);
+
+    assert!(camera_x(&mut app) >= PLATFORM_CAMERA_HALF_WIDTH);
+}
+
+#[test]
+fn platforming_with_two_players() {
+    let (mut app, _) = run_platforming(2);
+
+    assert!(camera_x(&mut app) >= PLATFORM_CAMERA_HALF_WIDTH);
+    assert_eq!(count::<Dog>(&mut app), 2);
+}
+
+#[test]
+fn pause_game_needs_a_started_player() {
+    let mut app = headless_app(GameState::Initial);
+    let mut system = IntoSystem::into_system(pause_game);
+    system.initialize(&mut app.world);
+
+    assert_eq!(system.run((), &mut app.world), ShouldRun::No);
+
+    let intro = app
+        .world
+        .spawn(Player(PlayerAction::Idle, LevelState::Intro))
+        .id();
+    assert_eq!(system.run((), &mut app.world), ShouldRun::No);
+
+    let started = Player(PlayerAction::Idle, LevelState::Start);
+    app.world.spawn(started);
+    assert_eq!(system.run((), &mut app.world), ShouldRun::Yes);
+
+    app.world.despawn(intro);
+    *app.world.resource_mut::<PauseEvent>() = PauseEvent::Paused;
+    assert_eq!(system.run((), &mut app.world), ShouldRun::No);
+}
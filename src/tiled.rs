@@ -0,0 +1,926 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::prelude::*;
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+/// Tiled stores flip flags in the top bits of every tile id
+const TILE_FLIP_FLAGS: u32 = 0xE000_0000;
+const DEFAULT_ENEMY_PATROL: f32 = 80.0;
+const DEFAULT_PICKUP_VALUE: u32 = 500;
+
+/// A Tiled map read from a `.tmx` or `.tmj`/`.json` file
+///
+/// Tile layers are kept for rendering while collision and object layers are turned into a
+/// `PlatformLevel` straight away so mistakes in the map fail the asset load.
+#[derive(TypeUuid, Debug)]
+#[uuid = "5b2c0f7e-6a0d-4f54-9a57-4b8b7d2f0c31"]
+pub struct TiledMap {
+    pub tile_size: Vec2,
+    /// Height of the map in tiles
+    pub height: u32,
+    pub tilesets: Vec<TiledTileset>,
+    pub tile_layers: Vec<TileLayer>,
+    pub level: PlatformLevel,
+}
+
+#[derive(Debug)]
+pub struct TiledTileset {
+    pub first_gid: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub image: Handle<Image>,
+}
+
+/// A tile spawned from one of the map's tile layers
+#[derive(Component)]
+pub struct Tile;
+
+#[derive(Debug)]
+pub struct TileLayer {
+    pub width: u32,
+    /// Global tile ids row by row starting at the top left, 0 being an empty cell
+    pub tiles: Vec<u32>,
+}
+
+/// Map contents before the tileset images have been turned into handles
+struct RawMap {
+    width: u32,
+    height: u32,
+    tile_width: f32,
+    tile_height: f32,
+    tilesets: Vec<RawTileset>,
+    layers: Vec<RawLayer>,
+}
+
+struct RawTileset {
+    first_gid: u32,
+    columns: u32,
+    tile_count: u32,
+    image: String,
+}
+
+enum RawLayer {
+    Tiles {
+        name: String,
+        tiles: Vec<u32>,
+        properties: HashMap<String, String>,
+        /// Hidden layers still collide but are not drawn
+        visible: bool,
+    },
+    Objects(Vec<RawObject>),
+}
+
+#[derive(Default)]
+struct RawObject {
+    name: String,
+    class: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    properties: HashMap<String, String>,
+}
+
+impl RawLayer {
+    /// Tile layers named `collision` or with a `collision` property set to true block movement
+    fn is_collision(&self) -> bool {
+        match self {
+            RawLayer::Tiles {
+                name, properties, ..
+            } => {
+                name.eq_ignore_ascii_case("collision")
+                    || properties.get("collision").map(String::as_str) == Some("true")
+            }
+            RawLayer::Objects(_) => false,
+        }
+    }
+}
+
+impl RawMap {
+    /// World position of a point given in Tiled pixels, the bottom of the map sitting at the
+    /// bottom of the screen
+    fn to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let map_height = self.height as f32 * self.tile_height;
+        let bottom = -WORLD_HEIGHT * CAMERA_SCALE / 2.0;
+
+        (x, bottom + map_height - y)
+    }
+
+    fn platform_level(&self) -> Result<PlatformLevel, String> {
+        let mut level = PlatformLevel {
+            width: self.width as f32 * self.tile_width,
+            spawn: self.to_world(self.tile_width, 0.0),
+            exit_x: (self.width as f32 - 1.0) * self.tile_width,
+            platforms: Vec::new(),
+            enemies: Vec::new(),
+            pickups: Vec::new(),
+            bombs: Vec::new(),
+        };
+
+        for layer in self.layers.iter() {
+            match layer {
+                RawLayer::Tiles { tiles, .. } if layer.is_collision() => {
+                    level.platforms.extend(self.colliders(tiles));
+                }
+                RawLayer::Tiles { .. } => (),
+                RawLayer::Objects(objects) => {
+                    for object in objects.iter() {
+                        self.place_object(object, &mut level)?;
+                    }
+                }
+            }
+        }
+
+        Ok(level)
+    }
+
+    /// Merges every horizontal run of solid tiles into a single platform, only drawn when the
+    /// map has no tileset to show the tiles with
+    fn colliders(&self, tiles: &[u32]) -> Vec<Platform> {
+        let mut platforms = Vec::new();
+
+        for (row, cells) in tiles.chunks(self.width.max(1) as usize).enumerate() {
+            let mut column = 0;
+
+            while column < cells.len() {
+                if cells[column] == 0 {
+                    column += 1;
+                    continue;
+                }
+
+                let start = column;
+                while column < cells.len() && cells[column] != 0 {
+                    column += 1;
+                }
+
+                let run = (column - start) as f32 * self.tile_width;
+                let position = self.to_world(
+                    start as f32 * self.tile_width + run / 2.0,
+                    (row as f32 + 0.5) * self.tile_height,
+                );
+
+                platforms.push(Platform {
+                    position,
+                    size: (run, self.tile_height),
+                    hidden: !self.tilesets.is_empty(),
+                });
+            }
+        }
+
+        platforms
+    }
+
+    fn place_object(&self, object: &RawObject, level: &mut PlatformLevel) -> Result<(), String> {
+        let center = self.to_world(
+            object.x + object.width / 2.0,
+            object.y + object.height / 2.0,
+        );
+        let property = |name: &str| object.properties.get(name);
+
+        match object.class.as_str() {
+            "PlayerSpawn" => level.spawn = center,
+            "Exit" => level.exit_x = center.0,
+            "Bomb" => level.bombs.push(center),
+            "Enemy" => {
+                let enemy_type = ron::from_str::<EnemyType>(&object.name)
+                    .map_err(|_| format!("unknown enemy archetype {:?}", object.name))?;
+                let patrol = match property("patrol") {
+                    Some(patrol) => patrol
+                        .parse::<f32>()
+                        .map_err(|_| format!("invalid patrol {:?}", patrol))?,
+                    None if object.width > 0.0 => object.width / 2.0,
+                    None => DEFAULT_ENEMY_PATROL,
+                };
+
+                level.enemies.push(PlatformEnemy {
+                    position: center,
+                    patrol,
+                    enemy_type,
+                });
+            }
+            "Pickup" => {
+                let value = match property("value") {
+                    Some(value) => value
+                        .parse::<u32>()
+                        .map_err(|_| format!("invalid pickup value {:?}", value))?,
+                    None => DEFAULT_PICKUP_VALUE,
+                };
+
+                level.pickups.push(PlatformPickup {
+                    position: center,
+                    value,
+                });
+            }
+            class => warn!("Ignoring Tiled object of unknown class {:?}", class),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: u32,
+    height: u32,
+    tilewidth: f32,
+    tileheight: f32,
+    layers: Vec<JsonLayer>,
+    tilesets: Vec<JsonTileset>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+    #[serde(default = "json_visible")]
+    visible: bool,
+    #[serde(default)]
+    data: Vec<u32>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    /// Called `class` since Tiled 1.9 and `type` before
+    #[serde(default, alias = "type")]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    firstgid: u32,
+    source: Option<String>,
+    image: Option<String>,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    tilecount: u32,
+}
+
+fn json_visible() -> bool {
+    true
+}
+
+fn json_properties(properties: &[JsonProperty]) -> HashMap<String, String> {
+    properties
+        .iter()
+        .map(|property| {
+            let value = match &property.value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+
+            (property.name.clone(), value)
+        })
+        .collect()
+}
+
+/// Flattens group layers, a hidden group hiding everything inside it
+fn json_layers(
+    layers: Vec<JsonLayer>,
+    visible: bool,
+    raw_layers: &mut Vec<RawLayer>,
+) -> Result<(), String> {
+    for layer in layers {
+        match layer.kind.as_str() {
+            "tilelayer" => raw_layers.push(RawLayer::Tiles {
+                properties: json_properties(&layer.properties),
+                name: layer.name,
+                tiles: layer.data,
+                visible: visible && layer.visible,
+            }),
+            "objectgroup" => raw_layers.push(RawLayer::Objects(
+                layer
+                    .objects
+                    .iter()
+                    .map(|object| RawObject {
+                        name: object.name.clone(),
+                        class: object.class.clone(),
+                        x: object.x,
+                        y: object.y,
+                        width: object.width,
+                        height: object.height,
+                        properties: json_properties(&object.properties),
+                    })
+                    .collect(),
+            )),
+            "group" => json_layers(layer.layers, visible && layer.visible, raw_layers)?,
+            // image layers are decoration only
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_json(source: &str) -> Result<RawMap, String> {
+    let map: JsonMap = serde_json::from_str(source).map_err(|error| error.to_string())?;
+
+    let mut layers = Vec::new();
+    json_layers(map.layers, true, &mut layers)?;
+
+    let tilesets = map
+        .tilesets
+        .into_iter()
+        .map(|tileset| match (tileset.image, tileset.source) {
+            (Some(image), _) => Ok(RawTileset {
+                first_gid: tileset.firstgid,
+                columns: tileset.columns,
+                tile_count: tileset.tilecount,
+                image,
+            }),
+            (None, Some(source)) => Err(format!(
+                "external tileset {:?} has to be embedded in the map",
+                source
+            )),
+            (None, None) => Err("only single image tilesets are supported".to_string()),
+        })
+        .collect::<Result<Vec<RawTileset>, String>>()?;
+
+    Ok(RawMap {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        tilesets,
+        layers,
+    })
+}
+
+/// A start or self-closing tag of a TMX file along with the text that follows it
+struct XmlTag<'a> {
+    name: &'a str,
+    attributes: HashMap<&'a str, String>,
+    closing: bool,
+    text: &'a str,
+}
+
+impl<'a> XmlTag<'a> {
+    fn attribute<T: std::str::FromStr>(&self, name: &str) -> Result<T, String> {
+        self.attributes
+            .get(name)
+            .ok_or_else(|| format!("<{}> is missing {:?}", self.name, name))?
+            .parse()
+            .map_err(|_| format!("<{}> has an invalid {:?}", self.name, name))
+    }
+
+    fn attribute_or<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.attributes.contains_key(name) {
+            true => self.attribute(name),
+            false => Ok(default),
+        }
+    }
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Splits a TMX file into its tags, which is all the structure Tiled maps need
+fn xml_tags(source: &str) -> Result<Vec<XmlTag>, String> {
+    let mut tags = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..]
+            .find('>')
+            .map(|end| start + end)
+            .ok_or("unterminated tag")?;
+        let content = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        if content.starts_with('?') || content.starts_with('!') {
+            continue;
+        }
+
+        let closing = content.starts_with('/');
+        let content = content.trim_start_matches('/').trim_end_matches('/');
+        let (name, mut attributes_source) = content
+            .split_once(char::is_whitespace)
+            .unwrap_or((content, ""));
+
+        let mut attributes = HashMap::new();
+        while let Some((key, value_source)) = attributes_source.split_once('=') {
+            let value_source = value_source.trim_start();
+            let quote = value_source
+                .chars()
+                .next()
+                .filter(|quote| *quote == '"' || *quote == '\'')
+                .ok_or_else(|| format!("unquoted attribute in <{}>", name))?;
+            let value_end = value_source[1..]
+                .find(quote)
+                .ok_or_else(|| format!("unterminated attribute in <{}>", name))?;
+
+            attributes.insert(key.trim(), unescape_xml(&value_source[1..value_end + 1]));
+            attributes_source = &value_source[value_end + 2..];
+        }
+
+        let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+        tags.push(XmlTag {
+            name,
+            attributes,
+            closing,
+            text,
+        });
+    }
+
+    Ok(tags)
+}
+
+fn parse_tmx(source: &str) -> Result<RawMap, String> {
+    let mut map = RawMap {
+        width: 0,
+        height: 0,
+        tile_width: 0.0,
+        tile_height: 0.0,
+        tilesets: Vec::new(),
+        layers: Vec::new(),
+    };
+
+    // properties belong to whatever element was opened last
+    let mut layer_properties = HashMap::new();
+    let mut layer_name = String::new();
+    let mut layer_visible = true;
+    // visibility of the enclosing groups, a hidden group hiding everything inside it
+    let mut groups_visible: Vec<bool> = Vec::new();
+    let mut tiles = Vec::new();
+    let mut objects: Option<Vec<RawObject>> = None;
+
+    for tag in xml_tags(source)? {
+        match (tag.name, tag.closing) {
+            ("map", false) => {
+                map.width = tag.attribute("width")?;
+                map.height = tag.attribute("height")?;
+                map.tile_width = tag.attribute("tilewidth")?;
+                map.tile_height = tag.attribute("tileheight")?;
+            }
+            ("tileset", false) => {
+                if let Some(source) = tag.attributes.get("source") {
+                    return Err(format!(
+                        "external tileset {:?} has to be embedded in the map",
+                        source
+                    ));
+                }
+
+                map.tilesets.push(RawTileset {
+                    first_gid: tag.attribute("firstgid")?,
+                    columns: tag.attribute_or("columns", 0)?,
+                    tile_count: tag.attribute_or("tilecount", 0)?,
+                    image: String::new(),
+                });
+            }
+            ("image", false) => {
+                if let Some(tileset) = map.tilesets.last_mut() {
+                    tileset.image = tag.attribute("source")?;
+                }
+            }
+            ("group", false) => groups_visible.push(tag.attribute_or("visible", 1)? != 0),
+            ("group", true) => {
+                groups_visible.pop();
+            }
+            ("layer", false) => {
+                layer_name = tag.attribute_or("name", String::new())?;
+                layer_visible = tag.attribute_or("visible", 1)? != 0
+                    && groups_visible.iter().all(|visible| *visible);
+                layer_properties = HashMap::new();
+                tiles = Vec::new();
+            }
+            ("data", false) => {
+                if tag.attributes.get("encoding").map(String::as_str) != Some("csv") {
+                    return Err("tile layers have to use CSV encoding".to_string());
+                }
+
+                tiles = tag
+                    .text
+                    .split(',')
+                    .map(|tile| tile.trim().parse::<u32>())
+                    .collect::<Result<Vec<u32>, _>>()
+                    .map_err(|error| error.to_string())?;
+            }
+            ("layer", true) => map.layers.push(RawLayer::Tiles {
+                name: std::mem::take(&mut layer_name),
+                tiles: std::mem::take(&mut tiles),
+                properties: std::mem::take(&mut layer_properties),
+                visible: layer_visible,
+            }),
+            ("objectgroup", false) => objects = Some(Vec::new()),
+            ("object", false) => {
+                if let Some(objects) = objects.as_mut() {
+                    objects.push(RawObject {
+                        name: tag.attribute_or("name", String::new())?,
+                        class: match tag.attributes.get("class") {
+                            Some(class) => class.clone(),
+                            None => tag.attribute_or("type", String::new())?,
+                        },
+                        x: tag.attribute("x")?,
+                        y: tag.attribute("y")?,
+                        width: tag.attribute_or("width", 0.0)?,
+                        height: tag.attribute_or("height", 0.0)?,
+                        ..default()
+                    });
+                }
+            }
+            ("objectgroup", true) => {
+                if let Some(objects) = objects.take() {
+                    map.layers.push(RawLayer::Objects(objects));
+                }
+            }
+            ("property", false) => {
+                let name = tag.attribute::<String>("name")?;
+                let value = tag.attribute_or("value", tag.text.trim().to_string())?;
+
+                match objects.as_mut().and_then(|objects| objects.last_mut()) {
+                    Some(object) => object.properties.insert(name, value),
+                    None => layer_properties.insert(name, value),
+                };
+            }
+            _ => (),
+        }
+    }
+
+    Ok(map)
+}
+
+#[derive(Default)]
+pub struct TiledMapLoader;
+
+impl AssetLoader for TiledMapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let source = std::str::from_utf8(bytes)?;
+            let is_tmx = load_context
+                .path()
+                .extension()
+                .map_or(false, |extension| extension == "tmx");
+
+            let raw_map = if is_tmx {
+                parse_tmx(source)
+            } else {
+                parse_json(source)
+            }
+            .map_err(bevy::asset::Error::msg)?;
+            let level = raw_map.platform_level().map_err(bevy::asset::Error::msg)?;
+
+            // tileset images are relative to the map file
+            let map_directory = load_context.path().parent().unwrap_or(Path::new(""));
+            let mut dependencies = Vec::new();
+            let tilesets = raw_map
+                .tilesets
+                .iter()
+                .map(|tileset| {
+                    let image_path = AssetPath::new(map_directory.join(&tileset.image), None);
+                    let image = load_context.get_handle(image_path.clone());
+                    dependencies.push(image_path);
+
+                    TiledTileset {
+                        first_gid: tileset.first_gid,
+                        columns: tileset.columns.max(1),
+                        tile_count: tileset.tile_count,
+                        image,
+                    }
+                })
+                .collect();
+
+            let tile_layers = raw_map
+                .layers
+                .iter()
+                .filter_map(|layer| match layer {
+                    RawLayer::Tiles {
+                        tiles,
+                        visible: true,
+                        ..
+                    } => Some(TileLayer {
+                        width: raw_map.width,
+                        tiles: tiles.clone(),
+                    }),
+                    RawLayer::Tiles { .. } | RawLayer::Objects(_) => None,
+                })
+                .collect();
+
+            let tiled_map = TiledMap {
+                tile_size: Vec2::new(raw_map.tile_width, raw_map.tile_height),
+                height: raw_map.height,
+                tilesets,
+                tile_layers,
+                level,
+            };
+            load_context
+                .set_default_asset(LoadedAsset::new(tiled_map).with_dependencies(dependencies));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx", "tmj", "json"]
+    }
+}
+
+impl TiledMap {
+    /// Spawns a sprite for every tile of the visible layers, later layers drawn on top
+    pub fn spawn_tiles(&self, commands: &mut Commands, texture_atlases: &mut Assets<TextureAtlas>) {
+        let atlases = self
+            .tilesets
+            .iter()
+            .map(|tileset| {
+                let rows = (tileset.tile_count / tileset.columns).max(1);
+                texture_atlases.add(TextureAtlas::from_grid(
+                    tileset.image.clone(),
+                    self.tile_size,
+                    tileset.columns as usize,
+                    rows as usize,
+                    None,
+                    None,
+                ))
+            })
+            .collect::<Vec<Handle<TextureAtlas>>>();
+
+        let bottom = -WORLD_HEIGHT * CAMERA_SCALE / 2.0;
+
+        for (depth, layer) in self.tile_layers.iter().enumerate() {
+            if layer.width == 0 {
+                continue;
+            }
+
+            for (index, gid) in layer.tiles.iter().enumerate() {
+                let gid = gid & !TILE_FLIP_FLAGS;
+                if gid == 0 {
+                    continue;
+                }
+
+                let tileset_index = match self
+                    .tilesets
+                    .iter()
+                    .rposition(|tileset| tileset.first_gid <= gid)
+                {
+                    Some(tileset_index) => tileset_index,
+                    None => continue,
+                };
+
+                let column = index as u32 % layer.width;
+                let row = index as u32 / layer.width;
+                let position = Vec2::new(
+                    (column as f32 + 0.5) * self.tile_size.x,
+                    bottom + (self.height - row) as f32 * self.tile_size.y - self.tile_size.y / 2.0,
+                );
+
+                commands.spawn((
+                    SpriteSheetBundle {
+                        sprite: TextureAtlasSprite::new(
+                            (gid - self.tilesets[tileset_index].first_gid) as usize,
+                        ),
+                        texture_atlas: atlases[tileset_index].clone(),
+                        transform: Transform::from_translation(
+                            position.extend(0.5 + depth as f32 * 0.01),
+                        ),
+                        ..default()
+                    },
+                    Tile,
+                ));
+            }
+        }
+    }
+}
+
+pub struct TiledPlugin;
+impl Plugin for TiledPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<TiledMap>()
+            .init_asset_loader::<TiledMapLoader>()
+            .add_system_set(SystemSet::on_exit(GameState::EndStage).with_system(despawner::<Tile>));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TMX_FIXTURE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.9" orientation="orthogonal" width="4" height="2" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="2" columns="2">
+  <image source="tiles.png" width="64" height="32"/>
+ </tileset>
+ <layer id="1" name="collision" width="4" height="2">
+  <data encoding="csv">
+0,0,0,0,
+1,1,0,2
+</data>
+ </layer>
+ <layer id="2" name="notes" width="4" height="2" visible="0">
+  <properties>
+   <property name="collision" value="false"/>
+  </properties>
+  <data encoding="csv">
+1,0,0,0,
+0,0,0,0
+</data>
+ </layer>
+ <group id="3" name="hidden" visible="0">
+  <layer id="4" name="decoration" width="4" height="2">
+   <data encoding="csv">
+0,1,0,0,
+0,0,0,0
+</data>
+  </layer>
+ </group>
+ <objectgroup id="5" name="objects">
+  <object id="1" type="PlayerSpawn" x="16" y="32"/>
+  <object id="2" name="ShieldNinja" class="Enemy" x="64" y="0" width="64" height="32">
+   <properties>
+    <property name="patrol" type="float" value="48"/>
+   </properties>
+  </object>
+  <object id="3" name="&quot;Tom &amp; Jerry&quot;" type="Pickup" x="96" y="16">
+   <properties>
+    <property name="value">250</property>
+   </properties>
+  </object>
+ </objectgroup>
+</map>
+"#;
+
+    #[test]
+    fn xml_tags_reads_attributes_text_and_closing_tags() {
+        let tags =
+            xml_tags(r#"<?xml version="1.0"?><!-- note --><a x='1' y="&lt;2&gt;">text</a><b/>"#)
+                .unwrap();
+
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[0].name, "a");
+        assert!(!tags[0].closing);
+        assert_eq!(tags[0].attributes["x"], "1");
+        assert_eq!(tags[0].attributes["y"], "<2>");
+        assert_eq!(tags[0].text, "text");
+        assert_eq!(tags[1].name, "a");
+        assert!(tags[1].closing);
+        assert_eq!(tags[2].name, "b");
+        assert!(!tags[2].closing);
+        assert!(tags[2].attributes.is_empty());
+    }
+
+    #[test]
+    fn xml_tags_rejects_broken_markup() {
+        assert!(xml_tags("<a x=1>").is_err());
+        assert!(xml_tags("<a x=\"1>").is_err());
+        assert!(xml_tags("<a").is_err());
+    }
+
+    #[test]
+    fn parses_tmx_fixture() {
+        let map = parse_tmx(TMX_FIXTURE).unwrap();
+
+        assert_eq!((map.width, map.height), (4, 2));
+        assert_eq!((map.tile_width, map.tile_height), (32.0, 32.0));
+        assert_eq!(map.tilesets.len(), 1);
+        assert_eq!(map.tilesets[0].first_gid, 1);
+        assert_eq!(map.tilesets[0].columns, 2);
+        assert_eq!(map.tilesets[0].tile_count, 2);
+        assert_eq!(map.tilesets[0].image, "tiles.png");
+
+        let tile_layers = map
+            .layers
+            .iter()
+            .filter_map(|layer| match layer {
+                RawLayer::Tiles {
+                    name,
+                    tiles,
+                    visible,
+                    ..
+                } => Some((name.as_str(), tiles.clone(), *visible)),
+                RawLayer::Objects(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tile_layers,
+            vec![
+                ("collision", vec![0, 0, 0, 0, 1, 1, 0, 2], true),
+                ("notes", vec![1, 0, 0, 0, 0, 0, 0, 0], false),
+                ("decoration", vec![0, 1, 0, 0, 0, 0, 0, 0], false),
+            ]
+        );
+
+        let objects = match map.layers.last() {
+            Some(RawLayer::Objects(objects)) => objects,
+            _ => panic!("the object layer comes last"),
+        };
+        assert_eq!(objects.len(), 3);
+        assert_eq!(objects[1].class, "Enemy");
+        assert_eq!(objects[1].properties["patrol"], "48");
+        assert_eq!(objects[2].name, "\"Tom & Jerry\"");
+        assert_eq!(objects[2].properties["value"], "250");
+    }
+
+    #[test]
+    fn builds_level_from_tmx_fixture() {
+        let level = parse_tmx(TMX_FIXTURE).unwrap().platform_level().unwrap();
+        let bottom = -WORLD_HEIGHT * CAMERA_SCALE / 2.0;
+
+        // only the collision layer blocks, split into two runs
+        assert_eq!(level.width, 128.0);
+        assert_eq!(level.platforms.len(), 2);
+        assert_eq!(level.platforms[0].position, (32.0, bottom + 16.0));
+        assert_eq!(level.platforms[0].size, (64.0, 32.0));
+        assert!(level.platforms[0].hidden);
+        assert_eq!(level.platforms[1].position, (112.0, bottom + 16.0));
+
+        assert_eq!(level.spawn, (16.0, bottom + 32.0));
+        assert_eq!(level.enemies.len(), 1);
+        assert_eq!(level.enemies[0].enemy_type, EnemyType::ShieldNinja);
+        assert_eq!(level.enemies[0].patrol, 48.0);
+        assert_eq!(level.enemies[0].position, (96.0, bottom + 48.0));
+        assert_eq!(level.pickups.len(), 1);
+        assert_eq!(level.pickups[0].value, 250);
+    }
+
+    #[test]
+    fn tmx_requires_csv_tile_data() {
+        let map = TMX_FIXTURE.replace("encoding=\"csv\"", "encoding=\"base64\"");
+
+        assert!(parse_tmx(&map).is_err());
+    }
+
+    #[test]
+    fn parses_tmj_fixture() {
+        let map = parse_json(include_str!("../assets/maps/stage_1_2.tmj")).unwrap();
+
+        assert_eq!((map.width, map.height), (75, 12));
+        assert_eq!(map.tilesets.len(), 1);
+        assert_eq!(map.tilesets[0].image, "tiles.png");
+        assert!(map.layers.iter().all(|layer| match layer {
+            RawLayer::Tiles { tiles, visible, .. } => *visible && tiles.len() == 75 * 12,
+            RawLayer::Objects(objects) => objects.len() == 12,
+        }));
+
+        let level = map.platform_level().unwrap();
+        assert!(!level.platforms.is_empty());
+        assert_eq!(level.enemies.len(), 4);
+        assert_eq!(level.pickups.len(), 3);
+        assert_eq!(level.bombs.len(), 3);
+    }
+
+    #[test]
+    fn tmj_hides_layers_inside_hidden_groups() {
+        let map = parse_json(
+            r#"{
+                "width": 2, "height": 1, "tilewidth": 16, "tileheight": 16, "tilesets": [],
+                "layers": [
+                    {"type": "tilelayer", "name": "shown", "data": [1, 0]},
+                    {"type": "tilelayer", "name": "hidden", "visible": false, "data": [0, 1]},
+                    {"type": "group", "name": "group", "visible": false, "layers": [
+                        {"type": "tilelayer", "name": "nested", "visible": true, "data": [1, 1]}
+                    ]}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let visible = map
+            .layers
+            .iter()
+            .map(|layer| match layer {
+                RawLayer::Tiles { name, visible, .. } => (name.as_str(), *visible),
+                RawLayer::Objects(_) => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            visible,
+            vec![("shown", true), ("hidden", false), ("nested", false)]
+        );
+    }
+}
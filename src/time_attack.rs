@@ -0,0 +1,216 @@
+use crate::{pause_game, prelude::*};
+use bevy::time::Stopwatch;
+use ron::de::from_bytes;
+use serde::{Deserialize, Serialize};
+
+pub const TIME_ATTACK_SAVE_FILE: &str = "time_attack.ron";
+
+/// Fixed set of waves every time attack run has to clear
+#[derive(Resource, Deserialize, Clone)]
+pub struct TimeAttackConfig {
+    pub waves: Vec<WaveData>,
+}
+
+/// Clock and split times of the current time attack run
+#[derive(Resource, Default)]
+pub struct TimeAttackRun {
+    pub clock: Stopwatch,
+    /// Elapsed seconds at the moment each wave was cleared
+    pub splits: Vec<f32>,
+    /// Splits of the personal best at the start of the run
+    pub ghost_splits: Vec<f32>,
+    /// Wave an enemy got away from, which ends the run without a time
+    pub escaped_wave: Option<usize>,
+    /// The run beat the personal best it started with
+    pub new_best: bool,
+}
+
+impl TimeAttackRun {
+    /// Seconds the run was ahead (negative) or behind (positive) the ghost at split `index`
+    pub fn delta(&self, index: usize) -> Option<f32> {
+        let split = self.splits.get(index)?;
+        let ghost_split = self.ghost_splits.get(index)?;
+
+        Some(split - ghost_split)
+    }
+}
+
+/// Fastest run saved locally, used as the ghost the current run is compared to
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct TimeAttackBest {
+    pub total_sec: Option<f32>,
+    pub splits: Vec<f32>,
+}
+
+pub struct TimeAttackPlugin;
+impl Plugin for TimeAttackPlugin {
+    fn build(&self, app: &mut App) {
+        let time_attack_config: TimeAttackConfig =
+            from_bytes(include_bytes!("../data/time_attack.ron")).unwrap();
+
+        if time_attack_config.waves.is_empty() {
+            panic!("data/time_attack.ron needs at least one wave");
+        }
+
+        for (index, wave) in time_attack_config.waves.iter().enumerate() {
+            if let Err(error) = wave.validate() {
+                panic!("Invalid wave {} in data/time_attack.ron: {}", index, error);
+            }
+        }
+
+        app.insert_resource(time_attack_config)
+            .insert_resource(load_save::<TimeAttackBest>(TIME_ATTACK_SAVE_FILE))
+            .init_resource::<TimeAttackRun>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::InGame).with_system(start_time_attack_run),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(time_attack_clock),
+            )
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(time_attack_splits))
+            .add_system_set(
+                SystemSet::on_enter(GameState::EndStage).with_system(time_attack_results_setup),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::EndStage)
+                    .with_system(despawner::<OnTimeAttackResultsScreen>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct OnTimeAttackResultsScreen;
+
+pub fn start_time_attack_run(
+    game_mode: Res<GameMode>,
+    time_attack_config: Res<TimeAttackConfig>,
+    time_attack_best: Res<TimeAttackBest>,
+    mut level: ResMut<Level>,
+    mut score: ResMut<Score>,
+    mut time_attack_run: ResMut<TimeAttackRun>,
+) {
+    if *game_mode != GameMode::TimeAttack {
+        return;
+    }
+
+    let waves = time_attack_config
+        .waves
+        .iter()
+        .map(|wave_data| Wave {
+            // the clock only stops once every enemy is dealt with
+            wait_for_clear: true,
+            ..Wave::from(wave_data)
+        })
+        .collect::<Vec<Wave>>();
+
+    *level = Level(waves, 0);
+    score.0 = 0;
+    *time_attack_run = TimeAttackRun {
+        ghost_splits: time_attack_best.splits.clone(),
+        ..default()
+    };
+}
+
+pub fn time_attack_clock(
+    time: Res<Time>,
+    game_mode: Res<GameMode>,
+    mut time_attack_run: ResMut<TimeAttackRun>,
+) {
+    if *game_mode == GameMode::TimeAttack {
+        time_attack_run.clock.tick(time.delta());
+    }
+}
+
+/// Records a split for every wave killed off and ends the run after the last one
+///
+/// Every enemy has to be killed, a wave that lets one get away ends the run without a time.
+pub fn time_attack_splits(
+    game_mode: Res<GameMode>,
+    time_attack_config: Res<TimeAttackConfig>,
+    mut time_attack_run: ResMut<TimeAttackRun>,
+    mut time_attack_best: ResMut<TimeAttackBest>,
+    mut game_state: ResMut<State<GameState>>,
+    mut wave_cleared_events: EventReader<WaveClearedEvent>,
+) {
+    if *game_mode != GameMode::TimeAttack {
+        wave_cleared_events.clear();
+        return;
+    }
+
+    for event in wave_cleared_events.iter() {
+        if !event.full_clear {
+            time_attack_run.escaped_wave = Some(event.number);
+            end_stage(&mut game_state);
+            break;
+        }
+
+        let split = time_attack_run.clock.elapsed_secs();
+        time_attack_run.splits.push(split);
+
+        if time_attack_run.splits.len() < time_attack_config.waves.len() {
+            continue;
+        }
+
+        let is_personal_best = time_attack_best
+            .total_sec
+            .map_or(true, |best_total| split < best_total);
+
+        if is_personal_best {
+            time_attack_best.total_sec = Some(split);
+            time_attack_best.splits = time_attack_run.splits.clone();
+            write_save(TIME_ATTACK_SAVE_FILE, &*time_attack_best);
+        }
+
+        time_attack_run.new_best = is_personal_best;
+        end_stage(&mut game_state);
+        break;
+    }
+}
+
+/// Final time and splits of the run, shown once the HUD and its clock are gone
+pub fn time_attack_results_setup(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    game_assets: Res<GameAssets>,
+    time_attack_config: Res<TimeAttackConfig>,
+    time_attack_run: Res<TimeAttackRun>,
+) {
+    if *game_mode != GameMode::TimeAttack {
+        return;
+    }
+
+    let waves_done = time_attack_run.splits.len();
+    let mut lines = match time_attack_run.escaped_wave {
+        Some(wave) => vec![format!("AN ENEMY ESCAPED IN WAVE {}", wave)],
+        // quit from the pause menu
+        None if waves_done < time_attack_config.waves.len() => {
+            vec![format!("QUIT IN WAVE {}", waves_done + 1)]
+        }
+        None => vec![format!(
+            "TIME {:.2}",
+            time_attack_run.splits.last().copied().unwrap_or_default()
+        )],
+    };
+
+    if time_attack_run.new_best {
+        lines.push("NEW BEST".to_string());
+    }
+
+    for (index, split) in time_attack_run.splits.iter().enumerate() {
+        let mut line = format!("WAVE {} {:.2}", index + 1, split);
+        if let Some(delta) = time_attack_run.delta(index) {
+            line += &format!(" {:+.2}", delta);
+        }
+        lines.push(line);
+    }
+
+    commands
+        .spawn((results_screen(), OnTimeAttackResultsScreen))
+        .with_children(|parent| {
+            for line in lines {
+                parent.spawn(results_text(line, &game_assets));
+            }
+        });
+}
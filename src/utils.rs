@@ -7,11 +7,14 @@ pub enum GameState {
     Transition,
     TitleScreen,
     MainMenu,
+    PracticeMenu,
     InGame,
+    Platforming,
     Paused,
     Loading,
     LoadWorld,
     EndStage,
+    GameComplete,
 }
 
 pub struct Bounds {
@@ -40,3 +43,11 @@ pub fn despawner<T: Component>(mut commands: Commands, to_despawn: Query<Entity,
         commands.entity(entity).despawn_recursive();
     }
 }
+
+/// Moves on to `EndStage`, unless the stage is already ending
+///
+/// Runs can end for several reasons on the same frame, like the boss dying as the last life is
+/// lost, and only the first one gets to queue the transition.
+pub fn end_stage(game_state: &mut State<GameState>) {
+    let _ = game_state.set(GameState::EndStage);
+}
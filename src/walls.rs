@@ -66,7 +66,8 @@ impl Plugin for WallPlugin {
             SystemSet::on_update(GameState::InGame)
                 .with_run_criteria(pause_game)
                 .with_system(wall_animator),
-        );
+        )
+        .add_system_set(SystemSet::on_exit(GameState::EndStage).with_system(despawner::<Wall>));
     }
 }
 
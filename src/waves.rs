@@ -4,27 +4,160 @@ use crate::{pause_game, prelude::*};
 use ron::de::from_bytes;
 use serde::Deserialize;
 
+pub const WAVE_CLEAR_BONUS: u32 = 500;
+pub const PERFECT_WAVE_BONUS: u32 = 1000;
+
 #[derive(Resource)]
 pub struct Level(pub Vec<Wave>, pub usize);
 
-struct LevelDifficultyMap {
-    waves: HashMap<WaveDifficulty, Vec<WaveData>>,
+/// Sent when a wave spawns its first formation
+pub struct WaveStartedEvent {
+    /// Starts at 1 for the first wave of the level
+    pub number: usize,
+}
+
+/// Sent once every enemy of a wave has been killed or has left the screen
+pub struct WaveClearedEvent {
+    pub number: usize,
+    /// Every enemy of the wave was killed
+    pub full_clear: bool,
+    /// Full clear without the player getting hit
+    pub perfect: bool,
+    pub bonus: u32,
 }
 
-#[derive(Deserialize, Debug, Hash, PartialEq, Eq, Clone)]
-enum WaveDifficulty {
-    Easy,
-    Medium,
-    Hard,
+/// Every wave of `data/waves.ron` grouped by difficulty
+#[derive(Resource)]
+pub struct LevelDifficultyMap {
+    pub waves: HashMap<Difficulty, Vec<WaveData>>,
 }
 
 #[derive(Deserialize, Clone)]
-struct WaveData {
+pub struct WaveData {
     pub enemy_type: EnemyType,
+    /// Number of formations spawned by the wave
     pub enemy_count: u32,
     pub enemy_interval_sec: f32,
     pub enemy_spawn_timer_sec: f32,
-    pub starting_wall: StartingWall,
+    pub starting_wall: WallPattern,
+    #[serde(default = "default_spawn_y")]
+    pub spawn_y: f32,
+    #[serde(default)]
+    pub formation: Formation,
+    #[serde(default)]
+    pub overrides: EnemyOverrides,
+    /// Holds back the next wave until every enemy of this one is gone
+    #[serde(default)]
+    pub wait_for_clear: bool,
+}
+
+fn default_spawn_y() -> f32 {
+    ENEMY_SPAWN_Y
+}
+
+impl WaveData {
+    /// Catches mistakes in wave data before they turn into odd behaviour mid-game
+    pub fn validate(&self) -> Result<(), String> {
+        let in_world = |x: f32, y: f32| {
+            (LEFT_WALL..=RIGHT_WALL).contains(&x)
+                && (-WORLD_HEIGHT / 2.0..=WORLD_HEIGHT / 2.0).contains(&y)
+        };
+
+        if self.enemy_count == 0 {
+            return Err("enemy_count must be at least 1".to_string());
+        }
+
+        if self.enemy_interval_sec <= 0.0 || self.enemy_spawn_timer_sec < 0.0 {
+            return Err("enemy_interval_sec must be positive and enemy_spawn_timer_sec must not be negative".to_string());
+        }
+
+        if !in_world(0.0, self.spawn_y) {
+            return Err(format!("spawn_y {} is outside of the world", self.spawn_y));
+        }
+
+        if let Formation::Pair { spacing } = self.formation {
+            if !in_world(0.0, self.spawn_y - spacing) {
+                return Err(format!(
+                    "pair spacing {} pushes the second enemy outside of the world",
+                    spacing
+                ));
+            }
+        }
+
+        if let Some(speed) = self.overrides.speed {
+            if speed <= 0.0 {
+                return Err(format!("speed override {} must be positive", speed));
+            }
+        }
+
+        if let Some((x, y)) = self.overrides.trajectory {
+            if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+                return Err("The trajectory must be between 0 and 1".to_string());
+            }
+        }
+
+        if let Some(path) = &self.overrides.path {
+            if path.waypoints.is_empty() || path.speed <= 0.0 {
+                return Err("paths need at least one waypoint and a positive speed".to_string());
+            }
+
+            if let Some((x, y)) = path.waypoints.iter().find(|(x, y)| !in_world(*x, *y)) {
+                return Err(format!("waypoint ({}, {}) is outside of the world", x, y));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Which wall each formation of a wave leaps from
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum WallPattern {
+    Left,
+    Right,
+    /// Starts on the left wall and switches every formation
+    Alternating,
+    Random,
+}
+
+impl WallPattern {
+    pub fn wall(&self, spawn_index: u32) -> StartingWall {
+        match self {
+            WallPattern::Left => StartingWall::Left,
+            WallPattern::Right => StartingWall::Right,
+            WallPattern::Alternating if spawn_index % 2 == 0 => StartingWall::Left,
+            WallPattern::Alternating => StartingWall::Right,
+            WallPattern::Random if random::<bool>() => StartingWall::Left,
+            WallPattern::Random => StartingWall::Right,
+        }
+    }
+}
+
+/// Enemies spawned together every time a wave spawns
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+pub enum Formation {
+    #[default]
+    Single,
+    /// Two enemies leaping from the same wall, the second `spacing` below the first
+    Pair { spacing: f32 },
+    /// One enemy from each wall at the same height
+    Mirrored,
+}
+
+impl Formation {
+    /// Wall and height of every enemy in the formation
+    pub fn positions(&self, starting_wall: StartingWall, spawn_y: f32) -> Vec<(StartingWall, f32)> {
+        match self {
+            Formation::Single => vec![(starting_wall, spawn_y)],
+            Formation::Pair { spacing } => {
+                vec![(starting_wall, spawn_y), (starting_wall, spawn_y - spacing)]
+            }
+            Formation::Mirrored => vec![
+                (StartingWall::Left, spawn_y),
+                (StartingWall::Right, spawn_y),
+            ],
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -33,7 +166,51 @@ pub struct Wave {
     pub enemy_count: WaveCount,
     pub enemy_interval: SpawnInterval,
     pub enemy_spawn_timer_sec: EnemySpawnTimer,
-    pub starting_wall: StartingWall,
+    pub starting_wall: WallPattern,
+    pub spawn_y: f32,
+    pub formation: Formation,
+    pub overrides: EnemyOverrides,
+    pub wait_for_clear: bool,
+    /// Formations spawned so far, used to alternate walls
+    pub spawned: u32,
+    /// Every enemy spawned by the wave, dead or alive
+    pub members: Vec<Entity>,
+    pub killed: usize,
+    pub player_hit: bool,
+    pub cleared: bool,
+}
+
+impl From<&WaveData> for Wave {
+    fn from(wave: &WaveData) -> Self {
+        Wave {
+            enemy_type: wave.enemy_type,
+            enemy_count: WaveCount(wave.enemy_count),
+            enemy_interval: SpawnInterval(Timer::from_seconds(
+                wave.enemy_interval_sec,
+                TimerMode::Repeating,
+            )),
+            enemy_spawn_timer_sec: EnemySpawnTimer(Timer::from_seconds(
+                wave.enemy_spawn_timer_sec,
+                TimerMode::Once,
+            )),
+            starting_wall: wave.starting_wall,
+            spawn_y: wave.spawn_y,
+            formation: wave.formation,
+            overrides: wave.overrides.clone(),
+            wait_for_clear: wave.wait_for_clear,
+            spawned: 0,
+            members: Vec::new(),
+            killed: 0,
+            player_hit: false,
+            cleared: false,
+        }
+    }
+}
+
+impl Wave {
+    pub fn is_active(&self) -> bool {
+        self.spawned > 0 && !self.cleared
+    }
 }
 
 #[derive(Resource, Deserialize, Clone, Debug)]
@@ -42,8 +219,10 @@ pub struct WaveCount(pub u32);
 pub fn wave_spawner(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
+    enemy_config: Res<EnemyConfig>,
     mut level_resource: ResMut<Level>,
     time: Res<Time>,
+    mut wave_started_events: EventWriter<WaveStartedEvent>,
 ) {
     let index = level_resource.1;
     let waves = &mut level_resource.0;
@@ -63,22 +242,130 @@ pub fn wave_spawner(
                 .just_finished()
                 && current_wave.enemy_count.0 > 0
             {
-                commands.spawn(
-                    EnemyBundle::new(
+                if current_wave.spawned == 0 {
+                    wave_started_events.send(WaveStartedEvent { number: index + 1 });
+                }
+
+                let starting_wall = current_wave.starting_wall.wall(current_wave.spawned);
+
+                for (starting_wall, spawn_y) in current_wave
+                    .formation
+                    .positions(starting_wall, current_wave.spawn_y)
+                {
+                    let enemy = spawn_enemy(
+                        &mut commands,
                         current_wave.enemy_type,
-                        game_assets,
-                        current_wave.starting_wall,
+                        &game_assets,
+                        starting_wall,
+                        spawn_y,
+                        &current_wave.overrides,
+                        &enemy_config,
                     )
-                    .unwrap(),
-                );
+                    .unwrap();
+                    current_wave.members.push(enemy);
+                }
+
+                current_wave.spawned += 1;
                 current_wave.enemy_count.0 = current_wave.enemy_count.0 - 1;
-            } else if current_wave.enemy_interval.0.just_finished() {
+            } else if current_wave.enemy_interval.0.just_finished() && !current_wave.wait_for_clear
+            {
                 level_resource.1 = level_resource.1 + 1;
             }
         }
     }
 }
 
+/// Tracks the enemies of every started wave and clears the wave once none of them are left
+///
+/// Runs after enemies are marked for despawn by dying or leaving the screen, and only while the
+/// game is unpaused.
+pub fn wave_progress(
+    mut level_resource: ResMut<Level>,
+    mut score: ResMut<Score>,
+    enemy_query: Query<&Enemy, Without<MarkDespawn>>,
+    mut enemy_killed_events: EventReader<EnemyKilledEvent>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    mut wave_cleared_events: EventWriter<WaveClearedEvent>,
+) {
+    let player_hit = player_hit_events.iter().count() > 0;
+    let current_index = level_resource.1;
+
+    for event in enemy_killed_events.iter() {
+        if let Some(wave) = level_resource
+            .0
+            .iter_mut()
+            .find(|wave| wave.members.contains(&event.entity))
+        {
+            wave.killed += 1;
+        }
+    }
+
+    let mut advance = false;
+
+    for (index, wave) in level_resource.0.iter_mut().enumerate() {
+        if !wave.is_active() {
+            continue;
+        }
+
+        wave.player_hit |= player_hit;
+
+        let is_alive = |entity: &Entity| {
+            enemy_query
+                .get(*entity)
+                .map_or(false, |enemy| enemy.0 != EnemyState::Dead)
+        };
+
+        if wave.enemy_count.0 > 0 || wave.members.iter().any(is_alive) {
+            continue;
+        }
+
+        wave.cleared = true;
+
+        let full_clear = wave.killed >= wave.members.len();
+        let perfect = full_clear && !wave.player_hit;
+        let bonus = match (full_clear, perfect) {
+            (true, true) => WAVE_CLEAR_BONUS + PERFECT_WAVE_BONUS,
+            (true, false) => WAVE_CLEAR_BONUS,
+            _ => 0,
+        };
+
+        score.0 += bonus;
+        wave_cleared_events.send(WaveClearedEvent {
+            number: index + 1,
+            full_clear,
+            perfect,
+            bonus,
+        });
+
+        if index == current_index && wave.wait_for_clear {
+            advance = true;
+        }
+    }
+
+    if advance {
+        level_resource.1 += 1;
+    }
+}
+
+/// Rebuilds the waves of the current difficulty every time a story stage starts
+pub fn reset_story_level(
+    game_mode: Res<GameMode>,
+    difficulty: Res<DifficultyConfig>,
+    level_difficulty_map: Res<LevelDifficultyMap>,
+    mut level: ResMut<Level>,
+) {
+    if *game_mode != GameMode::Story && *game_mode != GameMode::Coop {
+        return;
+    }
+
+    let wave_data = level_difficulty_map
+        .waves
+        .get(&difficulty.current)
+        .unwrap_or(&level_difficulty_map.waves[&Difficulty::Easy]);
+
+    *level = Level(wave_data.iter().map(Wave::from).collect(), 0);
+}
+
 pub struct WavePlugin;
 impl Plugin for WavePlugin {
     fn build(&self, app: &mut App) {
@@ -86,30 +373,34 @@ impl Plugin for WavePlugin {
             waves: from_bytes(include_bytes!("../data/waves.ron")).unwrap(),
         };
 
-        let easy_wave_data = &level_difficulty_map.waves[&WaveDifficulty::Easy];
-        let waves = easy_wave_data
-            .iter()
-            .map(|wave| {
-                return Wave {
-                    enemy_type: wave.enemy_type.clone(),
-                    enemy_count: WaveCount(wave.enemy_count),
-                    enemy_interval: SpawnInterval(Timer::from_seconds(
-                        wave.enemy_interval_sec,
-                        TimerMode::Repeating,
-                    )),
-                    enemy_spawn_timer_sec: EnemySpawnTimer(Timer::from_seconds(
-                        wave.enemy_spawn_timer_sec,
-                        TimerMode::Once,
-                    )),
-                    starting_wall: wave.starting_wall.clone(),
-                };
-            })
-            .collect::<Vec<Wave>>();
-
-        app.insert_resource(Level(waves, 0)).add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_run_criteria(pause_game)
-                .with_system(wave_spawner),
-        );
+        for (difficulty, waves) in level_difficulty_map.waves.iter() {
+            for (index, wave) in waves.iter().enumerate() {
+                if let Err(error) = wave.validate() {
+                    panic!(
+                        "Invalid {:?} wave {} in data/waves.ron: {}",
+                        difficulty, index, error
+                    );
+                }
+            }
+        }
+
+        let easy_wave_data = &level_difficulty_map.waves[&Difficulty::Easy];
+        let waves = easy_wave_data.iter().map(Wave::from).collect::<Vec<Wave>>();
+
+        app.insert_resource(Level(waves, 0))
+            .insert_resource(level_difficulty_map)
+            .add_event::<WaveStartedEvent>()
+            .add_event::<WaveClearedEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_run_criteria(pause_game)
+                    .with_system(wave_spawner)
+                    .with_system(
+                        wave_progress
+                            .after(enemy_movement)
+                            .after(enemy_death_system),
+                    ),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(reset_story_level));
     }
 }